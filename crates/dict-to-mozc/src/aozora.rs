@@ -0,0 +1,184 @@
+//! 青空文庫のテキストのルビの読み込み
+//!
+//! `｜漢字《かんじ》` または `漢字《かんじ》` のルビから、表記と読みの組を取り出す。
+//! `｜` がない場合は、`《` の直前に続く漢字をルビの対象とする。
+//! ディレクトリを指定した場合、その中のテキストファイルをすべて読み込み、
+//! 出現回数が多いものほどコストを低くする。求めたコストは、品詞コストの調整をせずにそのまま使う。
+//! レコードは `よみ,表記,コスト,品詞1,...,品詞6` の並びで、品詞はSudachiDictと同じ書式にする。
+use super::*;
+
+use encoding_rs::SHIFT_JIS;
+
+/// ルビの対象になる漢字かどうか
+fn is_kanji(c: char) -> bool {
+    matches!(c,
+        '\u{3400}'..='\u{4DBF}'
+        | '\u{4E00}'..='\u{9FFF}'
+        | '\u{F900}'..='\u{FAFF}'
+        | '\u{20000}'..='\u{2FFFF}'
+        | '々' | '〆' | '〇' | 'ヶ' | '※')
+}
+
+/// `［＃...］` の注記を除く。
+fn remove_annotations(line: &str) -> String {
+    regex_replace_all!(r"［＃[^］]*］", line, "").into_owned()
+}
+
+/// ルビの読みを平仮名にして、空白を除き、踊り字(ゝ、ゞ)を前の文字に戻す。
+fn normalize_reading(reading: &str) -> String {
+    let mut normalized = String::new();
+    for c in convert_to_hiragana(reading).chars() {
+        let c = match (c, normalized.chars().next_back()) {
+            ('ゝ', Some(prev)) => prev,
+            ('ゞ', Some(prev)) if "かきくけこさしすせそたちつてとはひふへほ".contains(prev) => {
+                char::from_u32(prev as u32 + 1).unwrap_or(prev)
+            }
+            (c, _) if c.is_whitespace() => continue,
+            (c, _) => c,
+        };
+        normalized.push(c);
+    }
+    normalized
+}
+
+/// 1行から、(表記, 読み)の組を取り出す。
+fn ruby_pairs(line: &str) -> Vec<(String, String)> {
+    let line = remove_annotations(line);
+    let mut pairs = Vec::new();
+    // ルビの対象を探すための、ルビを除いた本文
+    let mut base: Vec<char> = Vec::new();
+    let mut marker: Option<usize> = None;
+    // 前のルビの終わり。隣り合う語のルビ(`一番《いちばん》獰悪《どうあく》`)の対象を、ここより前に広げない。
+    let mut boundary = 0;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '｜' => marker = Some(base.len()),
+            '《' => {
+                let reading: String = chars.by_ref().take_while(|c| *c != '》').collect();
+                let start = marker.take().unwrap_or_else(|| {
+                    let run = base[boundary..]
+                        .iter()
+                        .rev()
+                        .take_while(|c| is_kanji(**c))
+                        .count();
+                    base.len() - run
+                });
+                boundary = base.len();
+                let notation: String = base[start..].iter().collect();
+                // ※は外字の注記なので、表記に使えない。
+                if !notation.is_empty() && !notation.contains('※') {
+                    pairs.push((notation, normalize_reading(&reading)));
+                }
+            }
+            c => base.push(c),
+        }
+    }
+    pairs
+}
+
+fn read_aozora(args: &Config, process: &mut dyn FnMut(&StringRecord)) -> io::Result<()> {
+    // (読み, 表記)ごとの出現回数
+    let mut counts = IndexMap::<(String, String), usize, RandomState>::default();
+    // 出現回数は、すべてのファイルを通して数える。
+    for path in input_files(args)? {
+        if args.debug > 1 {
+            eprintln!("Reading: {:?}", path);
+        }
+        // 青空文庫のテキストはShift_JIS。
+        for line in BufReader::new(open_text(&path, args, Some(SHIFT_JIS))?).lines() {
+            for (notation, reading) in ruby_pairs(&line?) {
+                *counts.entry((reading, notation)).or_default() += 1;
+            }
+        }
+    }
+
+    for ((reading, notation), count) in &counts {
+        let cost = frequency_to_cost(*count as f64).to_string();
+        let mut fields = vec![reading.as_str(), notation.as_str(), cost.as_str()];
+        fields.extend(args.aozora_word_class.split(','));
+        process(&StringRecord::from(fields));
+    }
+    Ok(())
+}
+
+/// 青空文庫のテキストのルビの読み込み
+/// 品詞はaozora_word_classで指定する。
+pub struct AozoraProcessor;
+impl DictionaryProcessor for AozoraProcessor {
+    fn should_skip(
+        &self,
+        _dict_values: &mut DictValues,
+        record: &StringRecord,
+        _args: &Config,
+    ) -> bool {
+        should_skip_common(_dict_values, record, _args, DictType::Aozora)
+    }
+
+    fn word_class_analyze(
+        &self,
+        _dict_values: &mut DictValues,
+        record: &StringRecord,
+        _args: &Config,
+    ) -> bool {
+        word_class_analyze_common(_dict_values, record, _args, DictType::Aozora)
+    }
+
+    fn read_records(
+        &self,
+        _args: &Config,
+        process: &mut dyn FnMut(&StringRecord),
+    ) -> Option<io::Result<()>> {
+        Some(read_aozora(_args, process))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_pairs(line: &str, expected: &[(&str, &str)]) {
+        let pairs = ruby_pairs(line);
+        let pairs: Vec<(&str, &str)> = pairs
+            .iter()
+            .map(|(n, r)| (n.as_str(), r.as_str()))
+            .collect();
+        assert_eq!(pairs, expected);
+    }
+
+    #[test]
+    fn ruby() {
+        assert_pairs(
+            "吾輩《わがはい》は猫である。名前はまだ｜無《な》い。",
+            &[("吾輩", "わがはい"), ("無", "な")],
+        );
+        // ｜で始まる範囲はかなを含んでもよく、注記は除く。
+        assert_pairs(
+            "｜お伽噺《おとぎばなし》［＃「お伽噺」に傍点］の国",
+            &[("お伽噺", "おとぎばなし")],
+        );
+        // 隣り合う語のルビは、前のルビの対象を含めない。
+        assert_pairs(
+            "一番《いちばん》獰悪《どうあく》な種族",
+            &[("一番", "いちばん"), ("獰悪", "どうあく")],
+        );
+        // 半角の|はルビの始まりではない。
+        assert_pairs("a|漢字《かんじ》", &[("漢字", "かんじ")]);
+        // 外字の注記を含む表記は使わない。
+        assert_pairs("※［＃「魚＋師」、第4水準2-93-37］《ぼら》", &[]);
+    }
+
+    #[test]
+    fn readings() {
+        assert_eq!(normalize_reading("カタカナ"), "かたかな");
+        assert_eq!(normalize_reading("いすゞ"), "いすず");
+        assert_eq!(normalize_reading("さゝ き"), "ささき");
+    }
+
+    #[test]
+    fn frequency_cost() {
+        assert_eq!(frequency_to_cost(1.0), DEFAULT_COST);
+        assert!(frequency_to_cost(10.0) < frequency_to_cost(2.0));
+        assert_eq!(frequency_to_cost(1e10), MIN_COST);
+    }
+}
@@ -0,0 +1,389 @@
+//! 圧縮ファイル(.gz, .bz2, .xz, .zst)とzipアーカイブの読み込み
+//!
+//! 拡張子ではなく、ファイルの先頭のマジックナンバーで型式を判定し、展開しながら読む。
+//! zipアーカイブは、globのパターンに合うファイル(指定があればそのファイルだけ)を展開して、1つにつなげる。
+//! パターンに合うファイルがなくても、ファイルが1つだけのアーカイブはそのファイルを読む。
+//! `-` は標準入力とする。
+use super::*;
+
+use std::fs::File;
+use std::io::{BufReader, Read, Seek};
+use std::sync::Mutex;
+use std::thread;
+
+const GZIP_MAGIC: &[u8] = &[0x1F, 0x8B];
+const BZIP2_MAGIC: &[u8] = b"BZh";
+const XZ_MAGIC: &[u8] = &[0xFD, b'7', b'z', b'X', b'Z', 0x00];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xB5, 0x2F, 0xFD];
+const ZIP_MAGIC: &[u8] = b"PK\x03\x04";
+/// 型式の判定に読む、先頭のバイト数
+const MAGIC_SIZE: u64 = 8;
+
+/// 展開しながら読む入力
+pub(crate) type Input = Box<dyn Read + Send>;
+
+fn zip_error(e: zip::result::ZipError) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
+
+/// 標準入力は一度しか読めないので、ヘッダー行などを先に読んだ分を残しておき、次に開いたときに先に返す。
+static STDIN_HEAD: Mutex<Vec<u8>> = Mutex::new(Vec::new());
+
+/// 標準入力を、残しておいた分から読む。
+/// keepの場合は、読んだ分も残しておく。
+struct StdinReader {
+    head: io::Cursor<Vec<u8>>,
+    keep: bool,
+}
+
+impl StdinReader {
+    fn new(keep: bool) -> Self {
+        let mut head = STDIN_HEAD.lock().unwrap_or_else(|e| e.into_inner());
+        let head = if keep {
+            head.clone()
+        } else {
+            std::mem::take(&mut *head)
+        };
+        Self {
+            head: io::Cursor::new(head),
+            keep,
+        }
+    }
+}
+
+impl Read for StdinReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let length = self.head.read(buf)?;
+        if length > 0 {
+            return Ok(length);
+        }
+        let length = io::stdin().lock().read(buf)?;
+        if self.keep {
+            STDIN_HEAD
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .extend_from_slice(&buf[..length]);
+        }
+        Ok(length)
+    }
+}
+
+/// 別のスレッドで展開した内容を、パイプから読む。
+/// 最後まで読んだところで、スレッドのエラーを返す。
+struct ThreadReader {
+    pipe: Option<io::PipeReader>,
+    thread: Option<thread::JoinHandle<io::Result<()>>>,
+}
+
+impl ThreadReader {
+    fn spawn(
+        write: impl FnOnce(&mut io::PipeWriter) -> io::Result<()> + Send + 'static,
+    ) -> io::Result<Self> {
+        let (pipe, mut writer) = io::pipe()?;
+        let thread = thread::spawn(move || write(&mut writer));
+        Ok(Self {
+            pipe: Some(pipe),
+            thread: Some(thread),
+        })
+    }
+
+    fn join(&mut self) -> io::Result<()> {
+        match self.thread.take() {
+            Some(thread) => thread
+                .join()
+                .unwrap_or_else(|_| Err(io::Error::other("decompression thread panicked"))),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Read for ThreadReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let length = match &mut self.pipe {
+            Some(pipe) => pipe.read(buf)?,
+            None => 0,
+        };
+        if length == 0 && !buf.is_empty() {
+            self.join()?;
+        }
+        Ok(length)
+    }
+}
+
+impl Drop for ThreadReader {
+    /// 途中でやめた場合は、パイプを閉じて書き込みを止めてから、スレッドの終了を待つ。
+    fn drop(&mut self) {
+        self.pipe.take();
+        let _ = self.join();
+    }
+}
+
+/// 読み込むzipのメンバーかどうか
+/// 指定がある場合はパス全体かファイル名が一致するもの、ない場合はファイル名がglobのパターンに合うもの。
+fn is_zip_member(name: &str, member: Option<&str>, glob: &str) -> bool {
+    let file_name = Path::new(name)
+        .file_name()
+        .and_then(|file_name| file_name.to_str())
+        .unwrap_or(name);
+    match member {
+        Some(member) => name == member || file_name == member,
+        None => file_name_match(glob, file_name),
+    }
+}
+
+/// zipアーカイブのメンバーを展開して、つなげて読む。
+fn read_zip<R: Read + Seek + Send + 'static>(
+    path: &Path,
+    archive: R,
+    member: Option<&str>,
+    glob: &str,
+    debug: usize,
+) -> io::Result<Input> {
+    let mut archive = zip::ZipArchive::new(archive).map_err(zip_error)?;
+    let files: Vec<usize> = (0..archive.len())
+        .filter(|i| {
+            archive
+                .name_for_index(*i)
+                .is_some_and(|name| !name.ends_with('/'))
+        })
+        .collect();
+    let mut members: Vec<usize> = files
+        .iter()
+        .copied()
+        .filter(|i| {
+            archive
+                .name_for_index(*i)
+                .is_some_and(|name| is_zip_member(name, member, glob))
+        })
+        .collect();
+    if members.is_empty() && member.is_none() && files.len() == 1 {
+        members = files;
+    }
+    if members.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            match member {
+                Some(member) => format!("{} not found in {:?}", member, path),
+                None => format!("no file matching {} in {:?} (use --zip-member)", glob, path),
+            },
+        ));
+    }
+    let path = path.to_path_buf();
+    let reader = ThreadReader::spawn(move |writer| {
+        let mut buf = vec![0u8; 64 * 1024];
+        for i in members {
+            let mut file = archive.by_index(i).map_err(zip_error)?;
+            if debug > 1 {
+                eprintln!("Reading: {:?} in {:?}", file.name(), path);
+            }
+            let mut last = b'\n';
+            loop {
+                let length = file.read(&mut buf)?;
+                if length == 0 {
+                    break;
+                }
+                writer.write_all(&buf[..length])?;
+                last = buf[length - 1];
+            }
+            // 次のメンバーの先頭の行とつながらないようにする。
+            if last != b'\n' {
+                writer.write_all(b"\n")?;
+            }
+        }
+        Ok(())
+    })?;
+    Ok(Box::new(reader))
+}
+
+/// 先頭のマジックナンバーで型式を判定し、展開しながら読む。
+fn decompress(input: Input, magic: &[u8]) -> io::Result<Input> {
+    let input: Input = if magic.starts_with(GZIP_MAGIC) {
+        Box::new(flate2::read::MultiGzDecoder::new(input))
+    } else if magic.starts_with(BZIP2_MAGIC) {
+        Box::new(bzip2::read::MultiBzDecoder::new(input))
+    } else if magic.starts_with(XZ_MAGIC) {
+        // lzma-rsは書き込み先への展開だけなので、別のスレッドで展開する。
+        Box::new(ThreadReader::spawn(move |writer| {
+            lzma_rs::xz_decompress(&mut BufReader::new(input), writer)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{:?}", e)))
+        })?)
+    } else if magic.starts_with(ZSTD_MAGIC) {
+        Box::new(
+            ruzstd::decoding::StreamingDecoder::new(input)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?,
+        )
+    } else {
+        input
+    };
+    Ok(input)
+}
+
+/// 先頭のバイトを読む。
+fn read_magic(input: &mut impl Read) -> io::Result<Vec<u8>> {
+    let mut magic = Vec::new();
+    input.take(MAGIC_SIZE).read_to_end(&mut magic)?;
+    Ok(magic)
+}
+
+fn open(
+    path: &Path,
+    member: Option<&str>,
+    glob: &str,
+    debug: usize,
+    keep: bool,
+) -> io::Result<Input> {
+    if path == Path::new(STDIN_PATH) {
+        let mut stdin = StdinReader::new(keep);
+        let magic = read_magic(&mut stdin)?;
+        let mut input = io::Cursor::new(magic.clone()).chain(stdin);
+        // zipは末尾の一覧から読むので、標準入力は先にすべて読み込む。
+        if magic.starts_with(ZIP_MAGIC) {
+            let mut bytes = Vec::new();
+            input.read_to_end(&mut bytes)?;
+            return read_zip(path, io::Cursor::new(bytes), member, glob, debug);
+        }
+        return decompress(Box::new(input), &magic);
+    }
+    let mut file = BufReader::new(File::open(path)?);
+    let magic = read_magic(&mut file)?;
+    file.rewind()?;
+    if magic.starts_with(ZIP_MAGIC) {
+        return read_zip(path, file, member, glob, debug);
+    }
+    decompress(Box::new(file), &magic)
+}
+
+/// ファイル(`-` の場合は標準入力)を開く。圧縮ファイルとzipアーカイブは展開しながら読む。
+/// zipアーカイブは、memberかglobのパターンに合うファイルを読む。
+pub(crate) fn open_file(
+    path: &Path,
+    member: Option<&str>,
+    glob: &str,
+    debug: usize,
+) -> io::Result<Input> {
+    open(path, member, glob, debug, false)
+}
+
+/// ヘッダー行などを先に読むために開く。
+/// 標準入力の場合、読んだ分は次にopen_fileで開いたときにもう一度読める。
+pub(crate) fn peek_file(
+    path: &Path,
+    member: Option<&str>,
+    glob: &str,
+    debug: usize,
+) -> io::Result<Input> {
+    open(path, member, glob, debug, true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &[u8] = "よみ,表記\nとうきょう,東京\n".as_bytes();
+
+    fn read_all(mut input: Input) -> io::Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        input.read_to_end(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    fn read_compressed(compressed: Vec<u8>) -> io::Result<Vec<u8>> {
+        let mut input = io::Cursor::new(compressed);
+        let magic = read_magic(&mut input)?;
+        input.rewind()?;
+        read_all(decompress(Box::new(input), &magic)?)
+    }
+
+    #[test]
+    fn compressed_streams() {
+        let mut gz = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        gz.write_all(TEXT).unwrap();
+        assert_eq!(read_compressed(gz.finish().unwrap()).unwrap(), TEXT);
+
+        let mut bz = bzip2::write::BzEncoder::new(Vec::new(), bzip2::Compression::default());
+        bz.write_all(TEXT).unwrap();
+        assert_eq!(read_compressed(bz.finish().unwrap()).unwrap(), TEXT);
+
+        let mut xz = Vec::new();
+        lzma_rs::xz_compress(&mut &TEXT[..], &mut xz).unwrap();
+        assert_eq!(read_compressed(xz.clone()).unwrap(), TEXT);
+        // 壊れたxzは、スレッドのエラーを返す。
+        xz.truncate(xz.len() / 2);
+        assert!(read_compressed(xz).is_err());
+
+        let mut zst = Vec::new();
+        ruzstd::encoding::compress(TEXT, &mut zst, ruzstd::encoding::CompressionLevel::Fastest);
+        assert_eq!(read_compressed(zst).unwrap(), TEXT);
+
+        assert_eq!(read_compressed(TEXT.to_vec()).unwrap(), TEXT);
+    }
+
+    fn zip_archive(files: &[(&str, &[u8])]) -> io::Cursor<Vec<u8>> {
+        let mut writer = zip::ZipWriter::new(io::Cursor::new(Vec::new()));
+        let options = zip::write::SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Stored);
+        for (name, bytes) in files {
+            if name.ends_with('/') {
+                writer.add_directory(*name, options).unwrap();
+            } else {
+                writer.start_file(*name, options).unwrap();
+                writer.write_all(bytes).unwrap();
+            }
+        }
+        let mut archive = writer.finish().unwrap();
+        archive.rewind().unwrap();
+        archive
+    }
+
+    fn read_zip_members(
+        files: &[(&str, &[u8])],
+        member: Option<&str>,
+        glob: &str,
+    ) -> io::Result<Vec<u8>> {
+        let archive = zip_archive(files);
+        read_all(read_zip(Path::new("test.zip"), archive, member, glob, 0)?)
+    }
+
+    #[test]
+    fn zip_members() {
+        let files: &[(&str, &[u8])] = &[
+            ("lex/", b""),
+            ("lex/a.csv", b"a,1"),
+            ("lex/b.CSV.gz", b"b,2\n"),
+            ("README.md", b"readme"),
+        ];
+        // globに合うメンバーを、改行を補ってつなげる。
+        assert_eq!(
+            read_zip_members(files, None, "*.csv").unwrap(),
+            b"a,1\nb,2\n"
+        );
+        assert_eq!(read_zip_members(files, None, "*.md").unwrap(), b"readme\n");
+        // 指定はパス全体かファイル名
+        assert_eq!(
+            read_zip_members(files, Some("a.csv"), "*.txt").unwrap(),
+            b"a,1\n"
+        );
+        assert_eq!(
+            read_zip_members(files, Some("lex/a.csv"), "*").unwrap(),
+            b"a,1\n"
+        );
+        let error = read_zip_members(files, None, "*.txt").unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::NotFound);
+        let error = read_zip_members(files, Some("c.csv"), "*.csv").unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::NotFound);
+        // ファイルが1つだけなら、パターンに合わなくても読む。
+        let single: &[(&str, &[u8])] = &[("dir/", b""), ("dir/words.txt", b"w,3\n")];
+        assert_eq!(read_zip_members(single, None, "*.csv").unwrap(), b"w,3\n");
+    }
+
+    #[test]
+    fn open_compressed_file() {
+        let path = std::env::temp_dir().join(format!("dict-to-mozc-{}.csv.gz", std::process::id()));
+        let mut gz = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        gz.write_all(TEXT).unwrap();
+        std::fs::write(&path, gz.finish().unwrap()).unwrap();
+        let bytes = read_all(open_file(&path, None, "*.csv", 0).unwrap());
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(bytes.unwrap(), TEXT);
+    }
+}
@@ -0,0 +1,160 @@
+//! ATOKでエクスポートした単語ファイルの読み込み
+//!
+//! `よみ\t表記\tATOKの品詞` の並びで、先頭に `!!ATOK_TANGO_TEXT_HEADER_1` のヘッダー行がある。
+//! ATOKの品詞は、id.defの品詞文字列へ置き換える。
+//! レコードは `よみ,表記,コスト,品詞1,...,品詞7` の並びになる。
+use super::*;
+
+use encoding_rs::SHIFT_JIS;
+
+/// ATOKの品詞が一覧にない場合の品詞
+const ATOK_DEFAULT_WORD_CLASS: &str = "名詞,一般,*,*,*,*,*";
+
+/// ATOKの品詞から、id.defの品詞文字列へ
+fn atok_word_class(word_class: &str) -> &'static str {
+    match word_class {
+        "名詞" | "短縮読み" => "名詞,一般,*,*,*,*,*",
+        "固有一般" | "固有商品" => "名詞,固有名詞,一般,*,*,*,*",
+        "固有人姓" => "名詞,固有名詞,人名,姓,*,*,*",
+        "固有人名" => "名詞,固有名詞,人名,名,*,*,*",
+        "固有人他" => "名詞,固有名詞,人名,一般,*,*,*",
+        "固有地名" => "名詞,固有名詞,地域,一般,*,*,*",
+        "固有組織" => "名詞,固有名詞,組織,*,*,*,*",
+        "サ変名詞" | "ザ変名詞" | "サ変形動" => "名詞,サ変接続,*,*,*,*,*",
+        "形動名詞" => "名詞,形容動詞語幹,*,*,*,*,*",
+        "副詞" => "副詞,一般,*,*,*,*,*",
+        "連体詞" => "連体詞,*,*,*,*,*,*",
+        "接続詞" => "接続詞,*,*,*,*,*,*",
+        "感動詞" | "独立語" => "感動詞,*,*,*,*,*,*",
+        "接頭語" => "接頭詞,名詞接続,*,*,*,*,*",
+        "冠数詞" => "接頭詞,数接続,*,*,*,*,*",
+        "接尾語" => "名詞,接尾,一般,*,*,*,*",
+        "接尾人名" => "名詞,接尾,人名,*,*,*,*",
+        "接尾地名" => "名詞,接尾,地域,*,*,*,*",
+        "助数詞" => "名詞,接尾,助数詞,*,*,*,*",
+        "数詞" => "名詞,数,*,*,*,*,*",
+        // 単漢字は、MS-IMEやCannaの単漢字と同じく名詞にする。
+        "単漢字" => "名詞,一般,*,*,*,*,*",
+        "顔文字" => "記号,一般,*,*,*,*,*",
+        "形容詞" => "形容詞,自立,*,*,形容詞・アウオ段,基本形,*",
+        "カ行五段" | "五段カ行" => "動詞,自立,*,*,五段・カ行イ音便,基本形,*",
+        "ガ行五段" | "五段ガ行" => "動詞,自立,*,*,五段・ガ行,基本形,*",
+        "サ行五段" | "五段サ行" => "動詞,自立,*,*,五段・サ行,基本形,*",
+        "タ行五段" | "五段タ行" => "動詞,自立,*,*,五段・タ行,基本形,*",
+        "ナ行五段" | "五段ナ行" => "動詞,自立,*,*,五段・ナ行,基本形,*",
+        "バ行五段" | "五段バ行" => "動詞,自立,*,*,五段・バ行,基本形,*",
+        "マ行五段" | "五段マ行" => "動詞,自立,*,*,五段・マ行,基本形,*",
+        "ラ行五段" | "五段ラ行" => "動詞,自立,*,*,五段・ラ行,基本形,*",
+        "ワ行五段" | "五段ワ行" => "動詞,自立,*,*,五段・ワ行促音便,基本形,*",
+        "一段動詞" => "動詞,自立,*,*,一段,基本形,*",
+        "カ変動詞" => "動詞,自立,*,*,カ変・来ル,基本形,*",
+        "サ変動詞" => "動詞,自立,*,*,サ変・スル,基本形,*",
+        "ザ変動詞" => "動詞,自立,*,*,サ変・−ズル,基本形,*",
+        _ => ATOK_DEFAULT_WORD_CLASS,
+    }
+}
+
+/// 単語ファイルの1行を、`よみ,表記,コスト,品詞...` のレコードに変換する。
+/// `!!`で始まるヘッダー行は読み飛ばす。
+fn atok_line_to_record(line: &str) -> Option<StringRecord> {
+    let line = line.trim_end_matches(['\r', '\n']);
+    if line.is_empty() || line.starts_with('!') {
+        return None;
+    }
+    let mut fields = line.split('\t');
+    let yomi = fields.next()?;
+    let notation = fields.next()?;
+    let word_class = atok_word_class(fields.next().unwrap_or("名詞"));
+    let mut record = vec![yomi, notation, ""];
+    record.extend(word_class.split(','));
+    Some(StringRecord::from(record))
+}
+
+fn read_atok(path: &Path, args: &Config, process: &mut dyn FnMut(&StringRecord)) -> io::Result<()> {
+    // BOMがなく、UTF-8でもなければ、Shift_JISとみなす。
+    for line in BufReader::new(open_text(path, args, Some(SHIFT_JIS))?).lines() {
+        if let Some(record) = atok_line_to_record(&line?) {
+            process(&record);
+        }
+    }
+    Ok(())
+}
+
+/// ATOKの単語ファイルの読み込み
+pub struct AtokProcessor;
+impl DictionaryProcessor for AtokProcessor {
+    fn should_skip(
+        &self,
+        _dict_values: &mut DictValues,
+        record: &StringRecord,
+        _args: &Config,
+    ) -> bool {
+        should_skip_common(_dict_values, record, _args, DictType::Atok)
+    }
+
+    fn word_class_analyze(
+        &self,
+        _dict_values: &mut DictValues,
+        record: &StringRecord,
+        _args: &Config,
+    ) -> bool {
+        word_class_analyze_common(_dict_values, record, _args, DictType::Atok)
+    }
+
+    fn read_records(
+        &self,
+        _args: &Config,
+        process: &mut dyn FnMut(&StringRecord),
+    ) -> Option<io::Result<()>> {
+        Some(for_each_input(_args, |path| {
+            read_atok(path, _args, process)
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn line_to_record() {
+        let record = atok_line_to_record("かく\t書く\tカ行五段\r").unwrap();
+        assert_eq!(
+            record.iter().collect::<Vec<_>>(),
+            vec![
+                "かく",
+                "書く",
+                "",
+                "動詞",
+                "自立",
+                "*",
+                "*",
+                "五段・カ行イ音便",
+                "基本形",
+                "*"
+            ]
+        );
+        let record = atok_line_to_record("やまだ\t山田\t固有人姓").unwrap();
+        assert_eq!(&record[4], "固有名詞");
+        assert_eq!(&record[6], "姓");
+        let record = atok_line_to_record("かん\t漢\t単漢字").unwrap();
+        assert_eq!(
+            record.iter().skip(3).collect::<Vec<_>>().join(","),
+            "名詞,一般,*,*,*,*,*"
+        );
+    }
+
+    #[test]
+    fn unknown_word_class_and_header() {
+        let record = atok_line_to_record("ほげ\tホゲ\t未知の品詞").unwrap();
+        assert_eq!(
+            record.iter().skip(3).collect::<Vec<_>>().join(","),
+            ATOK_DEFAULT_WORD_CLASS
+        );
+        let record = atok_line_to_record("ふが\tフガ").unwrap();
+        assert_eq!(&record[3], "名詞");
+        assert!(atok_line_to_record("!!ATOK_TANGO_TEXT_HEADER_1").is_none());
+        assert!(atok_line_to_record("よみだけ").is_none());
+        assert!(atok_line_to_record("").is_none());
+    }
+}
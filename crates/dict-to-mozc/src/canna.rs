@@ -0,0 +1,238 @@
+//! Canna/Anthy(cannadic)辞書の読み込み
+//!
+//! `よみ #CODE*頻度 表記 表記2 #CODE 表記3` の1行を、表記ごとのレコードに分割する。
+//! 品詞コードは hinshi-memo.txt の一覧をもとに、id.defの品詞文字列へ変換する。
+//! レコードは `よみ,表記,コスト,品詞1,...,品詞7` の並びになる。
+use super::*;
+
+/// 頻度1あたりに差し引くコスト
+const CANNA_FREQ_WEIGHT: i32 = 10;
+
+/// 品詞コードから、(語幹に付ける送り仮名, id.defの品詞文字列)を求める。
+/// 活用語尾だけのコード(kxo, sxiなど)は変換しない。
+fn canna_word_class(code: &str) -> Option<(&'static str, &'static str)> {
+    // 連用形が名詞になる動詞(K5rなど)は、元の活用の種類として扱う。
+    // C5rは「行く」の活用なので別扱い。
+    let code = match code.strip_suffix('r') {
+        Some(base) if base != "C5" && (base.ends_with('5') || base == "KS") => base,
+        _ => code,
+    };
+    let class = match code {
+        // 自立語
+        "CJ" => ("", "感動詞,*,*,*,*,*,*"),
+        "KY" => ("い", "形容詞,自立,*,*,形容詞・アウオ段,基本形,*"),
+        "RT" => ("", "連体詞,*,*,*,*,*,*"),
+        // 動詞
+        "K5" => ("く", "動詞,自立,*,*,五段・カ行イ音便,基本形,*"),
+        "C5r" => ("く", "動詞,自立,*,*,五段・カ行促音便,基本形,*"),
+        "G5" => ("ぐ", "動詞,自立,*,*,五段・ガ行,基本形,*"),
+        "S5" => ("す", "動詞,自立,*,*,五段・サ行,基本形,*"),
+        "T5" => ("つ", "動詞,自立,*,*,五段・タ行,基本形,*"),
+        "N5" => ("ぬ", "動詞,自立,*,*,五段・ナ行,基本形,*"),
+        "B5" => ("ぶ", "動詞,自立,*,*,五段・バ行,基本形,*"),
+        "M5" => ("む", "動詞,自立,*,*,五段・マ行,基本形,*"),
+        "R5" => ("る", "動詞,自立,*,*,五段・ラ行,基本形,*"),
+        "L5" => ("る", "動詞,自立,*,*,五段・ラ行特殊,基本形,*"),
+        "W5" => ("う", "動詞,自立,*,*,五段・ワ行促音便,基本形,*"),
+        "U5" => ("う", "動詞,自立,*,*,五段・ワ行ウ音便,基本形,*"),
+        "KS" => ("る", "動詞,自立,*,*,一段,基本形,*"),
+        "SX" => ("する", "動詞,自立,*,*,サ変・−スル,基本形,*"),
+        "ZX" => ("ずる", "動詞,自立,*,*,サ変・−ズル,基本形,*"),
+        "aru" => ("る", "動詞,自立,*,*,五段・ラ行,基本形,*"),
+        // 固有名詞
+        "CN" => ("", "名詞,固有名詞,地域,一般,*,*,*"),
+        "JN" => ("", "名詞,固有名詞,人名,一般,*,*,*"),
+        "KK" => ("", "名詞,固有名詞,組織,*,*,*,*"),
+        // 接頭語
+        "CNPRE" | "PRE" => ("", "接頭詞,名詞接続,*,*,*,*,*"),
+        "NNPRE" => ("", "接頭詞,数接続,*,*,*,*,*"),
+        // 接尾語
+        "CNSUC1" | "CNSUC2" => ("", "名詞,接尾,地域,*,*,*,*"),
+        "JNSUC" => ("", "名詞,接尾,人名,*,*,*,*"),
+        "JS" | "JSSUC" => ("", "名詞,接尾,助数詞,*,*,*,*"),
+        "SUC" | "SVSUC" | "D2T35" | "N2T35" => ("", "名詞,接尾,一般,*,*,*,*"),
+        "D2T16" | "N2T16" => ("", "名詞,接尾,形容動詞語幹,*,*,*,*"),
+        "D2T30" | "N2T30" => ("", "名詞,接尾,サ変接続,*,*,*,*"),
+        "D2KY" | "N2KY" => ("い", "形容詞,接尾,*,*,形容詞・アウオ段,基本形,*"),
+        // 数詞
+        "NN" | "N00" | "N01" | "N02" | "N03" | "N04" | "N05" => ("", "名詞,数,*,*,*,*,*"),
+        // 単漢字は、MS-IMEやATOKの単漢字と同じく名詞にする。
+        "KJ" => ("", "名詞,一般,*,*,*,*,*"),
+        // 括弧
+        "OPEN" => ("", "記号,括弧開,*,*,*,*,*"),
+        "CLOSE" => ("", "記号,括弧閉,*,*,*,*,*"),
+        // 名詞・形容動詞(ダ・ナ・ノ・ナリ)
+        // T0x,T1xは「な」が付くので形容動詞語幹、T3xは「する」が付くものをサ変接続とする。
+        code if code.starts_with("T0") || code.starts_with("T1") => {
+            ("", "名詞,形容動詞語幹,*,*,*,*,*")
+        }
+        "T30" | "T31" | "T32" => ("", "名詞,サ変接続,*,*,*,*,*"),
+        code if code.starts_with("T3") => ("", "名詞,一般,*,*,*,*,*"),
+        // 副詞・形容動詞(ト・タル)
+        code if code.starts_with('F') => ("", "副詞,一般,*,*,*,*,*"),
+        _ => return None,
+    };
+    Some(class)
+}
+
+/// `#T35*500` を品詞コードと頻度に分ける。頻度がなければ0とする。
+fn split_code(token: &str) -> (&str, i32) {
+    let token = token.trim_start_matches('#');
+    match token.split_once('*') {
+        Some((code, freq)) => (code, freq.parse().unwrap_or(0)),
+        None => (token, 0),
+    }
+}
+
+/// cannadicの1行を、表記ごとのレコードに変換する。
+fn canna_line_to_records(line: &str) -> Vec<StringRecord> {
+    let mut tokens = line.split_whitespace();
+    let yomi = match tokens.next() {
+        Some(yomi) if !yomi.starts_with('#') => yomi,
+        _ => return Vec::new(),
+    };
+
+    let mut records = Vec::new();
+    let mut current: Option<(&str, &str, i32)> = None;
+    for token in tokens {
+        if token.starts_with('#') {
+            let (code, freq) = split_code(token);
+            current =
+                canna_word_class(code).map(|(okurigana, word_class)| (okurigana, word_class, freq));
+            continue;
+        }
+        let Some((okurigana, word_class, freq)) = current else {
+            continue;
+        };
+        let pronunciation = format!("{}{}", yomi, okurigana);
+        let notation = format!("{}{}", token, okurigana);
+        let cost = (DEFAULT_COST - freq * CANNA_FREQ_WEIGHT)
+            .max(MIN_COST)
+            .to_string();
+        let mut fields = vec![pronunciation.as_str(), notation.as_str(), cost.as_str()];
+        fields.extend(word_class.split(','));
+        records.push(StringRecord::from(fields));
+    }
+    records
+}
+
+fn read_canna(
+    path: &Path,
+    args: &Config,
+    process: &mut dyn FnMut(&StringRecord),
+) -> io::Result<()> {
+    for line in BufReader::new(open_text(path, args, None)?).lines() {
+        for record in canna_line_to_records(&line?) {
+            process(&record);
+        }
+    }
+    Ok(())
+}
+
+/// Canna/Anthy(cannadic)辞書型式の読み込み
+/// 文字コードは、指定がなければBOMと内容から推測する。
+pub struct CannaProcessor;
+impl DictionaryProcessor for CannaProcessor {
+    fn should_skip(
+        &self,
+        _dict_values: &mut DictValues,
+        record: &StringRecord,
+        _args: &Config,
+    ) -> bool {
+        should_skip_common(_dict_values, record, _args, DictType::Canna)
+    }
+
+    fn word_class_analyze(
+        &self,
+        _dict_values: &mut DictValues,
+        record: &StringRecord,
+        _args: &Config,
+    ) -> bool {
+        word_class_analyze_common(_dict_values, record, _args, DictType::Canna)
+    }
+
+    fn read_records(
+        &self,
+        _args: &Config,
+        process: &mut dyn FnMut(&StringRecord),
+    ) -> Option<io::Result<()>> {
+        Some(for_each_input(_args, |path| {
+            read_canna(path, _args, process)
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn codes_and_frequencies() {
+        assert_eq!(split_code("#T35*500"), ("T35", 500));
+        assert_eq!(split_code("#K5"), ("K5", 0));
+        assert_eq!(split_code("#KY*x"), ("KY", 0));
+        assert_eq!(canna_word_class("K5r"), canna_word_class("K5"));
+        assert_eq!(
+            canna_word_class("C5r").unwrap().1,
+            "動詞,自立,*,*,五段・カ行促音便,基本形,*"
+        );
+        assert_eq!(
+            canna_word_class("T05").unwrap().1,
+            "名詞,形容動詞語幹,*,*,*,*,*"
+        );
+        assert_eq!(
+            canna_word_class("T30").unwrap().1,
+            "名詞,サ変接続,*,*,*,*,*"
+        );
+        assert_eq!(canna_word_class("T35").unwrap().1, "名詞,一般,*,*,*,*,*");
+        assert_eq!(canna_word_class("F12").unwrap().1, "副詞,一般,*,*,*,*,*");
+        assert_eq!(canna_word_class("KJ").unwrap().1, "名詞,一般,*,*,*,*,*");
+        assert!(canna_word_class("kxo").is_none());
+    }
+
+    #[test]
+    fn line_to_records() {
+        let records = canna_line_to_records("あい #T35*202 愛 藍 #T30*150 相 #kxo 無視");
+        let rows: Vec<Vec<&str>> = records.iter().map(|r| r.iter().collect()).collect();
+        assert_eq!(
+            rows,
+            vec![
+                vec![
+                    "あい", "愛", "3980", "名詞", "一般", "*", "*", "*", "*", "*"
+                ],
+                vec![
+                    "あい", "藍", "3980", "名詞", "一般", "*", "*", "*", "*", "*"
+                ],
+                vec![
+                    "あい",
+                    "相",
+                    "4500",
+                    "名詞",
+                    "サ変接続",
+                    "*",
+                    "*",
+                    "*",
+                    "*",
+                    "*"
+                ],
+            ]
+        );
+    }
+
+    #[test]
+    fn okurigana() {
+        let records = canna_line_to_records("か #K5*100 書 #KS 変");
+        assert_eq!(&records[0][0], "かく");
+        assert_eq!(&records[0][1], "書く");
+        assert_eq!(&records[1][0], "かる");
+        assert_eq!(&records[1][1], "変る");
+        assert_eq!(&records[1][7], "一段");
+    }
+
+    #[test]
+    fn comment_lines() {
+        assert!(canna_line_to_records("#comment").is_empty());
+        assert!(canna_line_to_records("").is_empty());
+        assert!(canna_line_to_records("よみ 表記だけ").is_empty());
+    }
+}
@@ -0,0 +1,171 @@
+//! Microsoft IME / Google日本語入力でエクスポートしたユーザー辞書の読み込み
+//!
+//! `よみ\t表記\t品詞\tコメント` の並びで、Microsoft IMEの場合はUTF-16LE(BOM付き)、
+//! 先頭に `!Microsoft IME Dictionary Tool` などのヘッダー行がある。
+//! 品詞名はMozcのユーザー辞書の品詞名に置き換え、Mozcユーザー辞書型式と同じように処理する。
+use super::*;
+
+use encoding_rs::SHIFT_JIS;
+
+/// Microsoft IMEの品詞名と、Mozcのユーザー辞書の品詞名
+const IME_WORD_CLASSES: [(&str, &str); 24] = [
+    ("さ変名詞", "名詞サ変"),
+    ("サ変名詞", "名詞サ変"),
+    ("形容動詞", "名詞形動"),
+    ("形動名詞", "名詞形動"),
+    ("組織名", "組織"),
+    ("会社名", "組織"),
+    ("接尾語", "接尾一般"),
+    ("人名接尾語", "接尾人名"),
+    ("地名接尾語", "接尾地名"),
+    ("単漢字", "名詞"),
+    ("カ行五段", "動詞カ行五段"),
+    ("ガ行五段", "動詞ガ行五段"),
+    ("サ行五段", "動詞サ行五段"),
+    ("タ行五段", "動詞タ行五段"),
+    ("ナ行五段", "動詞ナ行五段"),
+    ("バ行五段", "動詞バ行五段"),
+    ("マ行五段", "動詞マ行五段"),
+    ("ラ行五段", "動詞ラ行五段"),
+    ("ワ行五段", "動詞ワ行五段"),
+    ("一段動詞", "動詞一段"),
+    ("カ変動詞", "動詞カ変"),
+    ("サ変動詞", "動詞サ変"),
+    ("ザ変動詞", "動詞ザ変"),
+    ("ラ変動詞", "動詞ラ変"),
+];
+
+/// Microsoft IMEの品詞名から、Mozcのユーザー辞書の品詞名へ
+/// Google日本語入力の品詞名はMozcと同じなので、そのまま使う。
+fn ime_word_class(word_class: &str) -> &str {
+    IME_WORD_CLASSES
+        .iter()
+        .find(|(ime, _)| *ime == word_class)
+        .map_or(word_class, |(_, mozc)| mozc)
+}
+
+/// エクスポートされた1行を、Mozcユーザー辞書型式のレコードに変換する。
+/// `!`で始まるヘッダー行と`#`で始まるコメント行は読み飛ばす。
+fn ime_line_to_record(line: &str) -> Option<StringRecord> {
+    let line = line.trim_end_matches(['\r', '\n']);
+    if line.is_empty() || line.starts_with('!') || line.starts_with('#') {
+        return None;
+    }
+    let mut fields = line.split('\t');
+    let yomi = fields.next()?;
+    let notation = fields.next()?;
+    let word_class = ime_word_class(fields.next().unwrap_or("名詞"));
+    let comment = fields.next().unwrap_or("");
+    Some(StringRecord::from(vec![
+        yomi, notation, word_class, comment,
+    ]))
+}
+
+fn read_ime(path: &Path, args: &Config, process: &mut dyn FnMut(&StringRecord)) -> io::Result<()> {
+    // BOMがなく、UTF-8でもなければ、古いMicrosoft IMEのShift_JISとみなす。
+    for line in BufReader::new(open_text(path, args, Some(SHIFT_JIS))?).lines() {
+        if let Some(record) = ime_line_to_record(&line?) {
+            process(&record);
+        }
+    }
+    Ok(())
+}
+
+/// Microsoft IME / Google日本語入力のユーザー辞書の読み込み
+pub struct ImeDictProcessor;
+impl DictionaryProcessor for ImeDictProcessor {
+    fn should_skip(
+        &self,
+        _dict_values: &mut DictValues,
+        record: &StringRecord,
+        _args: &Config,
+    ) -> bool {
+        should_skip_common(_dict_values, record, _args, DictType::MozcUserDict)
+    }
+
+    fn word_class_analyze(
+        &self,
+        _dict_values: &mut DictValues,
+        record: &StringRecord,
+        _args: &Config,
+    ) -> bool {
+        MozcUserDictProcessor.word_class_analyze(_dict_values, record, _args)
+    }
+
+    fn read_records(
+        &self,
+        _args: &Config,
+        process: &mut dyn FnMut(&StringRecord),
+    ) -> Option<io::Result<()>> {
+        Some(for_each_input(_args, |path| read_ime(path, _args, process)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ime_word_classes_are_mapped() {
+        let mapping = create_word_class_mapping();
+        for (ime, mozc) in IME_WORD_CLASSES {
+            assert!(
+                mapping.get_first_id_def(&mozc.to_owned()).is_some(),
+                "{} => {} has no id.def word class",
+                ime,
+                mozc
+            );
+            assert!(
+                userdb::USER_POS.contains(&mozc),
+                "{} is not a user POS",
+                mozc
+            );
+        }
+    }
+
+    /// ユーザー辞書の品詞から、id.defのIDを求める。
+    fn user_word_class_id(id_def: &str, word_class: &str) -> i32 {
+        let (mut id_def, default_noun_id) = parse_id_def(id_def.as_bytes()).unwrap();
+        let mapping = create_word_class_mapping();
+        let mut class_map = IndexMap::<String, i32, RandomState>::default();
+        let class = u_search_word_class(&mapping, &mut id_def, word_class.to_owned());
+        id_expr(&class, &mut id_def, &mut class_map, default_noun_id)
+    }
+
+    #[test]
+    fn verb_word_class_ids() {
+        // Mozcのid.defと同じ書式の動詞の行
+        let id_def = "\
+            1843 名詞,一般,*,*,*,*,*\n\
+            50 動詞,自立,*,*,一段,基本形,*\n\
+            60 動詞,自立,*,*,五段・カ行イ音便,基本形,*\n\
+            68 動詞,自立,*,*,五段・ワ行促音便,基本形,*\n\
+            69 動詞,自立,*,*,サ変・スル,基本形,*\n\
+            70 動詞,自立,*,*,カ変・来ル,基本形,*\n\
+            71 動詞,自立,*,*,サ変・−ズル,基本形,*\n";
+        for (word_class, id) in [
+            ("動詞カ行五段", 60),
+            ("動詞ワ行五段", 68),
+            ("動詞一段", 50),
+            ("動詞サ変", 69),
+            ("動詞カ変", 70),
+            ("動詞ザ変", 71),
+        ] {
+            assert_eq!(user_word_class_id(id_def, word_class), id, "{}", word_class);
+        }
+    }
+
+    #[test]
+    fn ime_line() {
+        let record = ime_line_to_record("かんずる\t感ずる\tザ変動詞\tメモ\r").unwrap();
+        assert_eq!(record, vec!["かんずる", "感ずる", "動詞ザ変", "メモ"]);
+        let record = ime_line_to_record("ぐーぐる\tGoogle\t固有名詞").unwrap();
+        assert_eq!(record, vec!["ぐーぐる", "Google", "固有名詞", ""]);
+        // 単漢字は、ほかの形式の単漢字と同じく名詞にする。
+        let record = ime_line_to_record("かん\t漢\t単漢字").unwrap();
+        assert_eq!(record, vec!["かん", "漢", "名詞", ""]);
+        assert!(ime_line_to_record("!Microsoft IME Dictionary Tool").is_none());
+        assert!(ime_line_to_record("# comment").is_none());
+        assert!(ime_line_to_record("").is_none());
+    }
+}
@@ -0,0 +1,507 @@
+//! JMdict(JMdict_e.xmlなど)とJMnedictの読み込み
+//!
+//! `<entry>` ごとに、表記(`keb`)と読み(`reb`)の組み合わせをレコードにする。
+//! `re_restr` がある読みは指定された表記とだけ、`re_nokanji` がある読みはかな表記だけにする。
+//! 品詞は意味(`sense`)ごとにあり、`stagk`/`stagr` で限定された意味の品詞は、その表記や読みの組み合わせにだけ付ける。
+//! JMdictの品詞の実体参照(`&n;`, `&v5k;`など)はSudachiDictと同じ書式の品詞にして、品詞判定をSudachiDictと共通にする。
+//! レコードは `よみ,表記,コスト,品詞1,...,品詞6` の並びになる。
+//! JMnedictの名前の種類(`&surname;`, `&place;`など)はMozcのユーザー辞書の品詞名にして、
+//! `よみ,表記,コスト,品詞` の並びにする。
+use super::*;
+
+use crate::xml::{push_reference, push_text, xml_error};
+use quick_xml::Reader;
+use quick_xml::events::Event;
+
+/// 品詞がない見出しの品詞
+const JMDICT_NOUN: &str = "名詞,普通名詞,一般,*,*,*";
+/// 名前の種類がない見出しの品詞
+const JMNEDICT_PROPER_NOUN: &str = "固有名詞";
+
+/// JMdictの品詞の実体名から、SudachiDictの品詞へ
+/// 助詞・助動詞や古語の活用など、変換しないものはNoneを返す。
+fn jmdict_word_class(entity: &str) -> Option<&'static str> {
+    let class = match entity {
+        "n" | "adj-no" | "exp" => JMDICT_NOUN,
+        "n-adv" | "n-t" => "名詞,普通名詞,副詞可能,*,*,*",
+        "vs" => "名詞,普通名詞,サ変可能,*,*,*",
+        "num" => "名詞,数詞,*,*,*,*",
+        "pn" => "代名詞,*,*,*,*,*",
+        "adj-na" => "形状詞,一般,*,*,*,*",
+        "adj-t" | "adv-to" => "形状詞,タリ,*,*,*,*",
+        "adj-i" | "adj-ix" => "形容詞,一般,*,*,形容詞,終止形-一般",
+        "adj-pn" => "連体詞,*,*,*,*,*",
+        "adv" => "副詞,*,*,*,*,*",
+        "conj" => "接続詞,*,*,*,*,*",
+        "int" => "感動詞,一般,*,*,*,*",
+        "pref" => "接頭辞,*,*,*,*,*",
+        "suf" => "接尾辞,名詞的,一般,*,*,*",
+        "ctr" => "接尾辞,名詞的,助数詞,*,*,*",
+        // 動詞
+        "v5k" | "v5k-s" => "動詞,一般,*,*,五段-カ行,終止形-一般",
+        "v5g" => "動詞,一般,*,*,五段-ガ行,終止形-一般",
+        "v5s" => "動詞,一般,*,*,五段-サ行,終止形-一般",
+        "v5t" => "動詞,一般,*,*,五段-タ行,終止形-一般",
+        "v5n" => "動詞,一般,*,*,五段-ナ行,終止形-一般",
+        "v5b" => "動詞,一般,*,*,五段-バ行,終止形-一般",
+        "v5m" => "動詞,一般,*,*,五段-マ行,終止形-一般",
+        "v5r" | "v5r-i" | "v5aru" => "動詞,一般,*,*,五段-ラ行,終止形-一般",
+        "v5u" | "v5u-s" => "動詞,一般,*,*,五段-ワ行,終止形-一般",
+        // v1は上一段と下一段の区別がないので、行を指定しない。
+        "v1" | "v1-s" => "動詞,一般,*,*,下一段,終止形-一般",
+        "vk" => "動詞,一般,*,*,カ行変格,終止形-一般",
+        "vs-s" | "vs-i" | "vs-c" | "vz" => "動詞,一般,*,*,サ行変格,終止形-一般",
+        _ => return None,
+    };
+    Some(class)
+}
+
+/// JMnedictの名前の種類から、Mozcのユーザー辞書の品詞名へ
+fn jmnedict_word_class(entity: &str) -> Option<&'static str> {
+    let class = match entity {
+        "surname" => "姓",
+        "given" | "masc" | "fem" => "名",
+        "person" => "人名",
+        "place" | "station" => "地名",
+        "company" | "organization" => "組織",
+        _ => JMNEDICT_PROPER_NOUN,
+    };
+    Some(class)
+}
+
+/// JMdictとJMnedictで異なる部分
+struct JmdictKind {
+    /// 意味を示す要素名
+    sense_element: &'static [u8],
+    /// 品詞を示す要素名
+    pos_element: &'static [u8],
+    /// 品詞の実体名から品詞へ
+    word_class: fn(&str) -> Option<&'static str>,
+    /// 品詞がない見出しの品詞
+    default_word_class: &'static str,
+}
+
+const JMDICT: JmdictKind = JmdictKind {
+    sense_element: b"sense",
+    pos_element: b"pos",
+    word_class: jmdict_word_class,
+    default_word_class: JMDICT_NOUN,
+};
+
+const JMNEDICT: JmdictKind = JmdictKind {
+    sense_element: b"trans",
+    pos_element: b"name_type",
+    word_class: jmnedict_word_class,
+    default_word_class: JMNEDICT_PROPER_NOUN,
+};
+
+#[derive(Default)]
+struct JmdictKanji {
+    keb: String,
+    /// 検索用の表記(`&sK;`)
+    search_only: bool,
+}
+
+#[derive(Default)]
+struct JmdictReading {
+    reb: String,
+    /// この読みを使う表記(`re_restr`)。空ならすべての表記。
+    restr: Vec<String>,
+    /// 表記と結び付かない読み(`re_nokanji`)
+    nokanji: bool,
+    /// 検索用の読み(`&sk;`)
+    search_only: bool,
+}
+
+/// 意味(JMnedictでは`trans`)ごとの品詞
+#[derive(Default)]
+struct JmdictSense {
+    word_classes: Vec<&'static str>,
+    /// 品詞の指定があったかどうか
+    has_pos: bool,
+    /// この意味の表記(`stagk`)。空ならすべての表記。
+    stagk: Vec<String>,
+    /// この意味の読み(`stagr`)。空ならすべての読み。
+    stagr: Vec<String>,
+    /// 通常かな表記(`&uk;`)
+    usually_kana: bool,
+}
+
+impl JmdictSense {
+    /// 読みと表記(かな表記の場合はNone)の組み合わせに当てはまる意味かどうか
+    fn applies(&self, reb: &str, keb: Option<&str>) -> bool {
+        (self.stagr.is_empty() || self.stagr.iter().any(|r| r == reb))
+            && keb.is_none_or(|keb| self.stagk.is_empty() || self.stagk.iter().any(|k| k == keb))
+    }
+}
+
+#[derive(Default)]
+struct JmdictEntry {
+    kanji: Vec<JmdictKanji>,
+    readings: Vec<JmdictReading>,
+    senses: Vec<JmdictSense>,
+}
+
+impl JmdictEntry {
+    /// 品詞の指定がない意味は、前の意味の品詞を引き継ぐ。
+    fn end_sense(&mut self) {
+        if let [.., previous, sense] = self.senses.as_mut_slice()
+            && !sense.has_pos
+        {
+            sense.has_pos = previous.has_pos;
+            sense.word_classes = previous.word_classes.clone();
+        }
+    }
+
+    /// 読みと表記の組み合わせに当てはまる意味の品詞
+    /// どの意味にも品詞の指定がなければ、kindの既定の品詞にする。
+    fn word_classes(
+        &self,
+        kind: &JmdictKind,
+        reb: &str,
+        keb: Option<&str>,
+        kana_only: bool,
+    ) -> Vec<&'static str> {
+        let mut word_classes = Vec::new();
+        let mut has_pos = false;
+        // 表記のある読みのかな表記は、通常かな表記の意味だけにする。
+        let senses = self
+            .senses
+            .iter()
+            .filter(|sense| sense.applies(reb, keb))
+            .filter(|sense| keb.is_some() || kana_only || sense.usually_kana);
+        let mut applied = false;
+        for sense in senses {
+            applied = true;
+            has_pos |= sense.has_pos;
+            for word_class in &sense.word_classes {
+                if !word_classes.contains(word_class) {
+                    word_classes.push(*word_class);
+                }
+            }
+        }
+        // 変換しない品詞だけの場合は、レコードにしない。
+        if (applied || self.senses.is_empty()) && !has_pos {
+            word_classes.push(kind.default_word_class);
+        }
+        word_classes
+    }
+
+    /// 読みと表記の組み合わせごとに、当てはまる品詞の数だけレコードにする。
+    fn records(&self, kind: &JmdictKind) -> Vec<StringRecord> {
+        let mut records = Vec::new();
+        for reading in self.readings.iter().filter(|r| !r.search_only) {
+            let kana_only = self.kanji.is_empty() || reading.nokanji;
+            let mut notations: Vec<Option<&str>> = vec![None];
+            if !reading.nokanji {
+                notations.extend(
+                    self.kanji
+                        .iter()
+                        .filter(|k| !k.search_only)
+                        .filter(|k| reading.restr.is_empty() || reading.restr.contains(&k.keb))
+                        .map(|k| Some(k.keb.as_str())),
+                );
+            }
+            for keb in notations {
+                let notation = keb.unwrap_or(&reading.reb);
+                for word_class in self.word_classes(kind, &reading.reb, keb, kana_only) {
+                    let mut fields = vec![reading.reb.as_str(), notation, ""];
+                    fields.extend(word_class.split(','));
+                    records.push(StringRecord::from(fields));
+                }
+            }
+        }
+        records
+    }
+}
+
+fn read_jmdict(
+    path: &Path,
+    args: &Config,
+    kind: &JmdictKind,
+    process: &mut dyn FnMut(&StringRecord),
+) -> io::Result<()> {
+    // JMdict_e.gzのように圧縮されていてもよい。
+    parse_jmdict(BufReader::new(open_input(path, args)?), kind, process)
+}
+
+/// JMdict/JMnedictのXMLから、見出しごとのレコードをprocessへ渡す。
+fn parse_jmdict(
+    input: impl io::BufRead,
+    kind: &JmdictKind,
+    process: &mut dyn FnMut(&StringRecord),
+) -> io::Result<()> {
+    let mut reader = Reader::from_reader(input);
+    let mut buf = Vec::new();
+    let mut element: Vec<u8> = Vec::new();
+    let mut text = String::new();
+    let mut entry = JmdictEntry::default();
+    loop {
+        match reader.read_event_into(&mut buf).map_err(xml_error)? {
+            Event::Start(e) => {
+                element = e.name().as_ref().to_vec();
+                text.clear();
+                match element.as_slice() {
+                    b"entry" => entry = JmdictEntry::default(),
+                    b"k_ele" => entry.kanji.push(JmdictKanji::default()),
+                    b"r_ele" => entry.readings.push(JmdictReading::default()),
+                    sense if sense == kind.sense_element => {
+                        entry.senses.push(JmdictSense::default())
+                    }
+                    _ => {}
+                }
+            }
+            Event::Empty(e) => {
+                if e.name().as_ref() == b"re_nokanji"
+                    && let Some(reading) = entry.readings.last_mut()
+                {
+                    reading.nokanji = true;
+                }
+            }
+            Event::Text(e) => push_text(&mut text, &e)?,
+            // 品詞などは、実体参照の名前で判定する。
+            Event::GeneralRef(e) => {
+                let name = e.decode().map_err(xml_error)?;
+                match element.as_slice() {
+                    pos if pos == kind.pos_element => {
+                        if let Some(sense) = entry.senses.last_mut() {
+                            sense.has_pos = true;
+                            if let Some(word_class) = (kind.word_class)(&name)
+                                && !sense.word_classes.contains(&word_class)
+                            {
+                                sense.word_classes.push(word_class);
+                            }
+                        }
+                    }
+                    b"misc" if name == "uk" => {
+                        if let Some(sense) = entry.senses.last_mut() {
+                            sense.usually_kana = true;
+                        }
+                    }
+                    b"ke_inf" if name == "sK" => {
+                        if let Some(kanji) = entry.kanji.last_mut() {
+                            kanji.search_only = true;
+                        }
+                    }
+                    b"re_inf" if name == "sk" => {
+                        if let Some(reading) = entry.readings.last_mut() {
+                            reading.search_only = true;
+                        }
+                    }
+                    _ => push_reference(&mut text, &e)?,
+                }
+            }
+            Event::End(e) => {
+                match e.name().as_ref() {
+                    b"keb" => {
+                        if let Some(kanji) = entry.kanji.last_mut() {
+                            kanji.keb = std::mem::take(&mut text);
+                        }
+                    }
+                    b"reb" => {
+                        if let Some(reading) = entry.readings.last_mut() {
+                            reading.reb = std::mem::take(&mut text);
+                        }
+                    }
+                    b"re_restr" => {
+                        if let Some(reading) = entry.readings.last_mut() {
+                            reading.restr.push(std::mem::take(&mut text));
+                        }
+                    }
+                    b"stagk" => {
+                        if let Some(sense) = entry.senses.last_mut() {
+                            sense.stagk.push(std::mem::take(&mut text));
+                        }
+                    }
+                    b"stagr" => {
+                        if let Some(sense) = entry.senses.last_mut() {
+                            sense.stagr.push(std::mem::take(&mut text));
+                        }
+                    }
+                    sense if sense == kind.sense_element => entry.end_sense(),
+                    b"entry" => {
+                        for record in entry.records(kind) {
+                            process(&record);
+                        }
+                    }
+                    _ => {}
+                }
+                element.clear();
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+    Ok(())
+}
+
+/// JMdictの読み込み
+pub struct JmdictProcessor;
+impl DictionaryProcessor for JmdictProcessor {
+    fn should_skip(
+        &self,
+        _dict_values: &mut DictValues,
+        record: &StringRecord,
+        _args: &Config,
+    ) -> bool {
+        should_skip_common(_dict_values, record, _args, DictType::Jmdict)
+    }
+
+    fn word_class_analyze(
+        &self,
+        _dict_values: &mut DictValues,
+        record: &StringRecord,
+        _args: &Config,
+    ) -> bool {
+        word_class_analyze_common(_dict_values, record, _args, DictType::Jmdict)
+    }
+
+    fn read_records(
+        &self,
+        _args: &Config,
+        process: &mut dyn FnMut(&StringRecord),
+    ) -> Option<io::Result<()>> {
+        Some(for_each_input(_args, |path| {
+            read_jmdict(path, _args, &JMDICT, process)
+        }))
+    }
+}
+
+/// JMnedictの読み込み
+/// 地名は-p/--placesを指定した場合だけ含める。
+pub struct JmnedictProcessor;
+impl DictionaryProcessor for JmnedictProcessor {
+    fn should_skip(
+        &self,
+        _dict_values: &mut DictValues,
+        record: &StringRecord,
+        _args: &Config,
+    ) -> bool {
+        should_skip_common(_dict_values, record, _args, DictType::Jmnedict)
+    }
+
+    fn word_class_analyze(
+        &self,
+        _dict_values: &mut DictValues,
+        record: &StringRecord,
+        _args: &Config,
+    ) -> bool {
+        word_class_analyze_common(_dict_values, record, _args, DictType::Jmnedict)
+    }
+
+    fn read_records(
+        &self,
+        _args: &Config,
+        process: &mut dyn FnMut(&StringRecord),
+    ) -> Option<io::Result<()>> {
+        Some(for_each_input(_args, |path| {
+            read_jmdict(path, _args, &JMNEDICT, process)
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(xml: &str, kind: &JmdictKind) -> Vec<Vec<String>> {
+        let mut records = Vec::new();
+        parse_jmdict(xml.as_bytes(), kind, &mut |record| {
+            records.push(record.iter().map(str::to_string).collect());
+        })
+        .unwrap();
+        records
+    }
+
+    fn pairs(records: &[Vec<String>]) -> Vec<(&str, &str, &str)> {
+        records
+            .iter()
+            .map(|r| (r[0].as_str(), r[1].as_str(), r[3].as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn sense_restrictions() {
+        // 「明く」は「あく」だけ、「開く」の他動詞は「ひらく」だけ
+        let xml = "<JMdict><entry>\
+            <k_ele><keb>開く</keb></k_ele><k_ele><keb>明く</keb></k_ele>\
+            <r_ele><reb>あく</reb></r_ele>\
+            <r_ele><reb>ひらく</reb><re_restr>開く</re_restr></r_ele>\
+            <sense><stagr>あく</stagr><pos>&v5k;</pos><gloss>to open</gloss></sense>\
+            <sense><stagk>開く</stagk><stagr>ひらく</stagr><pos>&v5k;</pos><pos>&vt;</pos></sense>\
+            <sense><stagk>開く</stagk><pos>&n;</pos></sense>\
+            </entry></JMdict>";
+        let records = parse(xml, &JMDICT);
+        assert_eq!(
+            pairs(&records),
+            [
+                ("あく", "開く", "動詞"),
+                ("あく", "開く", "名詞"),
+                ("あく", "明く", "動詞"),
+                ("ひらく", "開く", "動詞"),
+                ("ひらく", "開く", "名詞"),
+            ]
+        );
+    }
+
+    #[test]
+    fn pos_carries_over_and_usually_kana() {
+        // 品詞のない2番目の意味は前の品詞を引き継ぎ、かな表記は&uk;の意味だけにする。
+        let xml = "<JMdict><entry>\
+            <k_ele><keb>此処</keb></k_ele><k_ele><keb>茲</keb><ke_inf>&sK;</ke_inf></k_ele>\
+            <r_ele><reb>ここ</reb></r_ele>\
+            <sense><pos>&pn;</pos><misc>&uk;</misc></sense>\
+            <sense><pos>&n;</pos></sense>\
+            <sense><misc>&uk;</misc></sense>\
+            </entry></JMdict>";
+        let records = parse(xml, &JMDICT);
+        assert_eq!(
+            pairs(&records),
+            [
+                ("ここ", "ここ", "代名詞"),
+                ("ここ", "ここ", "名詞"),
+                ("ここ", "此処", "代名詞"),
+                ("ここ", "此処", "名詞"),
+            ]
+        );
+    }
+
+    #[test]
+    fn unconverted_pos_and_nokanji() {
+        let xml = "<JMdict><entry>\
+            <r_ele><reb>だ</reb></r_ele><sense><pos>&cop;</pos></sense>\
+            </entry><entry>\
+            <k_ele><keb>人</keb></k_ele>\
+            <r_ele><reb>ひと</reb></r_ele>\
+            <r_ele><reb>ヒト</reb><re_nokanji/></r_ele>\
+            <sense><gloss>person</gloss></sense>\
+            </entry></JMdict>";
+        let records = parse(xml, &JMDICT);
+        assert_eq!(
+            pairs(&records),
+            [("ひと", "人", "名詞"), ("ヒト", "ヒト", "名詞")]
+        );
+        assert_eq!(records[0][3..], ["名詞", "普通名詞", "一般", "*", "*", "*"]);
+    }
+
+    #[test]
+    fn jmnedict_name_types() {
+        let xml = "<JMnedict><entry>\
+            <k_ele><keb>田中</keb></k_ele><r_ele><reb>たなか</reb></r_ele>\
+            <trans><name_type>&surname;</name_type><name_type>&place;</name_type></trans>\
+            </entry><entry>\
+            <k_ele><keb>東京</keb></k_ele><r_ele><reb>とうきょう</reb></r_ele>\
+            <trans><name_type>&unclass;</name_type></trans>\
+            </entry></JMnedict>";
+        let records = parse(xml, &JMNEDICT);
+        assert_eq!(
+            pairs(&records),
+            [
+                ("たなか", "田中", "姓"),
+                ("たなか", "田中", "地名"),
+                ("とうきょう", "東京", "固有名詞"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,198 @@
+//! JSON / JSONL型式の単語リストの読み込み
+//!
+//! 1単語を1つのオブジェクトとし、JSONの配列、または1行に1オブジェクトを並べる(JSONL)。
+//! 読み、表記、品詞、コスト(または頻度)の値は、キー名かJSON Pointer(`/word/reading` など)で指定する。
+//! 品詞はSudachiDictと同じ書式の文字列(`名詞,普通名詞,一般,*,*,*`)か、その配列にする。
+//! レコードは `よみ,表記,コスト,品詞1,...,品詞6` の並びになる。
+use super::*;
+
+use encoding_rs::UTF_8;
+use serde_json::Value;
+
+/// 品詞の指定がない単語の品詞
+const JSON_DEFAULT_WORD_CLASS: &str = "名詞,普通名詞,一般,*,*,*";
+/// 品詞の階層の数
+const WORD_CLASS_DEPTH: usize = 6;
+
+/// キー名、または `/` から始まるJSON Pointerで値を取り出す。
+fn json_field<'a>(object: &'a Value, key: &str) -> Option<&'a Value> {
+    if key.starts_with('/') {
+        object.pointer(key)
+    } else {
+        object.get(key)
+    }
+}
+
+/// 文字列か数値の値を文字列にする。
+fn json_string(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.to_owned()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+/// 品詞を、カンマ区切りの文字列か配列から取り出す。足りない階層は `*` で埋める。
+fn json_word_class(value: Option<&Value>) -> Vec<String> {
+    let mut parts: Vec<String> = match value {
+        Some(Value::Array(parts)) => parts.iter().filter_map(json_string).collect(),
+        Some(value) => json_string(value)
+            .map(|s| s.split(',').map(|part| part.trim().to_owned()).collect())
+            .unwrap_or_default(),
+        None => Vec::new(),
+    };
+    if parts.iter().all(|part| part.is_empty()) {
+        parts = JSON_DEFAULT_WORD_CLASS
+            .split(',')
+            .map(String::from)
+            .collect();
+    }
+    if parts.len() < WORD_CLASS_DEPTH {
+        parts.resize(WORD_CLASS_DEPTH, "*".to_owned());
+    }
+    parts
+}
+
+/// コストの値があればそのまま、頻度の値があればコストに換算する。
+/// どちらも品詞コストの調整はしない。
+fn json_cost(object: &Value, args: &Config) -> i32 {
+    if let Some(cost) = args
+        .json_cost
+        .as_ref()
+        .and_then(|key| json_field(object, key))
+        .and_then(json_string)
+        .and_then(|s| s.parse::<f64>().ok())
+    {
+        return cost as i32;
+    }
+    args.json_frequency
+        .as_ref()
+        .and_then(|key| json_field(object, key))
+        .and_then(json_string)
+        .and_then(|s| s.parse::<f64>().ok())
+        .map_or(DEFAULT_COST, frequency_to_cost)
+}
+
+/// 1単語のオブジェクトを、`よみ,表記,コスト,品詞...` のレコードにする。
+fn json_record(object: &Value, args: &Config) -> Option<StringRecord> {
+    let pronunciation = json_field(object, &args.json_pronunciation).and_then(json_string)?;
+    let notation = json_field(object, &args.json_notation).and_then(json_string)?;
+    let cost = json_cost(object, args).to_string();
+    let mut fields = vec![pronunciation, notation, cost];
+    fields.extend(json_word_class(json_field(object, &args.json_word_class)));
+    Some(StringRecord::from(fields))
+}
+
+fn read_json(args: &Config, process: &mut dyn FnMut(&StringRecord)) -> io::Result<()> {
+    for_each_input(args, |path| {
+        let input = BufReader::new(open_text(path, args, Some(UTF_8))?);
+        // JSONの配列も、JSONLのように値が続くものも読めるようにする。
+        let values = serde_json::Deserializer::from_reader(input).into_iter::<Value>();
+        for value in values {
+            let value = value.map_err(io::Error::from)?;
+            let objects = match value {
+                Value::Array(objects) => objects,
+                object => vec![object],
+            };
+            for object in &objects {
+                match json_record(object, args) {
+                    Some(record) => process(&record),
+                    None => {
+                        if args.debug > 1 {
+                            eprintln!("Missing reading or notation: {}", object);
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    })
+}
+
+/// JSON / JSONL型式の単語リストの読み込み
+/// 列の位置の代わりに、json_pronunciationなどのキーで値を取り出す。
+pub struct JsonProcessor;
+impl DictionaryProcessor for JsonProcessor {
+    fn should_skip(
+        &self,
+        _dict_values: &mut DictValues,
+        record: &StringRecord,
+        _args: &Config,
+    ) -> bool {
+        should_skip_common(_dict_values, record, _args, DictType::Json)
+    }
+
+    fn word_class_analyze(
+        &self,
+        _dict_values: &mut DictValues,
+        record: &StringRecord,
+        _args: &Config,
+    ) -> bool {
+        word_class_analyze_common(_dict_values, record, _args, DictType::Json)
+    }
+
+    fn read_records(
+        &self,
+        _args: &Config,
+        process: &mut dyn FnMut(&StringRecord),
+    ) -> Option<io::Result<()>> {
+        Some(read_json(_args, process))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> Config {
+        Config {
+            json_pronunciation: "/word/kana".to_owned(),
+            json_notation: "surface".to_owned(),
+            json_word_class: "pos".to_owned(),
+            json_cost: Some("cost".to_owned()),
+            json_frequency: Some("freq".to_owned()),
+            ..Default::default()
+        }
+    }
+
+    fn record(json: &str) -> Option<Vec<String>> {
+        let object: Value = serde_json::from_str(json).unwrap();
+        json_record(&object, &config()).map(|record| record.iter().map(str::to_string).collect())
+    }
+
+    #[test]
+    fn pointer_and_word_class() {
+        assert_eq!(
+            record(r#"{"word": {"kana": "トウキョウ"}, "surface": "東京", "pos": ["名詞", "固有名詞", "地名"], "cost": 3000}"#).unwrap(),
+            ["トウキョウ", "東京", "3000", "名詞", "固有名詞", "地名", "*", "*", "*"]
+        );
+        assert_eq!(
+            record(r#"{"word": {"kana": "ねこ"}, "surface": "猫", "pos": "名詞,普通名詞,一般"}"#)
+                .unwrap()[3..],
+            ["名詞", "普通名詞", "一般", "*", "*", "*"]
+        );
+        // 品詞がなければ普通名詞、読みがなければ読まない。
+        assert_eq!(
+            record(r#"{"word": {"kana": "いぬ"}, "surface": "犬"}"#).unwrap()[3..],
+            ["名詞", "普通名詞", "一般", "*", "*", "*"]
+        );
+        assert!(record(r#"{"surface": "犬"}"#).is_none());
+    }
+
+    #[test]
+    fn cost_and_frequency() {
+        let cost = |json: &str| record(json).unwrap()[2].clone();
+        assert_eq!(
+            cost(r#"{"word": {"kana": "a"}, "surface": "a", "cost": "1234", "freq": 100}"#),
+            "1234"
+        );
+        assert_eq!(
+            cost(r#"{"word": {"kana": "a"}, "surface": "a", "freq": 100}"#),
+            frequency_to_cost(100.0).to_string()
+        );
+        assert_eq!(
+            cost(r#"{"word": {"kana": "a"}, "surface": "a"}"#),
+            DEFAULT_COST.to_string()
+        );
+    }
+}
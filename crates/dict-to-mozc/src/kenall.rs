@@ -0,0 +1,205 @@
+//! 日本郵便の郵便番号データ(KEN_ALL.CSV)の読み込み
+//!
+//! 都道府県名・市区町村名・町域名を、半角カタカナの読みから地名のレコードにする。
+//! あわせて、7桁の郵便番号を読み、住所を表記としたレコードも作る。
+//! レコードは `よみ,表記,コスト,品詞1,...,品詞7` の並びになる。
+use super::*;
+
+use encoding_rs::SHIFT_JIS;
+
+/// 地名の品詞
+const KEN_ALL_WORD_CLASS: &str = "名詞,固有名詞,地域,一般,*,*,*";
+
+/// 郵便番号の列
+const POSTAL_CODE: usize = 2;
+/// 都道府県名・市区町村名・町域名の読みと表記の列
+const ADDRESS_COLUMNS: [(usize, usize); 3] = [(3, 6), (4, 7), (5, 8)];
+
+/// 町域名から、括弧書きの部分と、町域を表さない記載を除く。
+/// `以下に掲載がない場合` や `○○町一円` のような記載は、空にする。
+fn town_name(town: &str, open: char) -> &str {
+    let town = town.split(open).next().unwrap_or("");
+    if town == "以下に掲載がない場合"
+        || town == "ｲｶﾆｹｲｻｲｶﾞﾅｲﾊﾞｱｲ"
+        || town.ends_with("の次に番地がくる場合")
+        || town.ends_with("ﾉﾂｷﾞﾆﾊﾞﾝﾁｶﾞｸﾙﾊﾞｱｲ")
+        || (town.ends_with("一円") && town != "一円")
+        || (town.ends_with("ｲﾁｴﾝ") && town != "ｲﾁｴﾝ")
+    {
+        return "";
+    }
+    town
+}
+
+fn ken_all_record(pronunciation: &str, notation: &str, cost: &str) -> StringRecord {
+    let mut fields = vec![pronunciation, notation, cost];
+    fields.extend(KEN_ALL_WORD_CLASS.split(','));
+    StringRecord::from(fields)
+}
+
+/// KEN_ALL.CSVの1行を、地名ごとのレコードと郵便番号のレコードに変換する。
+fn ken_all_records(record: &StringRecord) -> Vec<StringRecord> {
+    let mut records = Vec::new();
+    let mut address = String::new();
+    for (i, (kana_index, name_index)) in ADDRESS_COLUMNS.into_iter().enumerate() {
+        let (Some(kana), Some(name)) = (record.get(kana_index), record.get(name_index)) else {
+            return Vec::new();
+        };
+        // 町域名だけは、括弧書きなどを除く。
+        let (kana, name) = if i == ADDRESS_COLUMNS.len() - 1 {
+            (town_name(kana, '('), town_name(name, '（'))
+        } else {
+            (kana, name)
+        };
+        if kana.is_empty() || name.is_empty() {
+            continue;
+        }
+        address.push_str(name);
+        records.push(ken_all_record(&convert_to_hiragana(kana), name, ""));
+    }
+    if let Some(postal_code) = record.get(POSTAL_CODE)
+        && !address.is_empty()
+    {
+        records.push(ken_all_record(postal_code, &address, ""));
+    }
+    records
+}
+
+fn read_ken_all(
+    path: &Path,
+    args: &Config,
+    process: &mut dyn FnMut(&StringRecord),
+) -> io::Result<()> {
+    // 日本郵便の配布ファイルはShift_JIS。UTF-8版(utf_ken_all.csv)もそのまま読める。
+    parse_ken_all(open_text(path, args, Some(SHIFT_JIS))?, process)
+}
+
+/// KEN_ALL.CSVの内容から、地名と郵便番号のレコードをprocessへ渡す。
+fn parse_ken_all(input: impl io::Read, process: &mut dyn FnMut(&StringRecord)) -> io::Result<()> {
+    let mut reader = ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(input);
+    // 町域名が長い場合、括弧書きの途中で複数行に分かれている。
+    // 括弧書きは使わないので、閉じ括弧までの続きの行は読み飛ばす。
+    let mut in_parentheses = false;
+    for result in reader.records() {
+        let record = result?;
+        let town = record.get(ADDRESS_COLUMNS[2].1).unwrap_or("");
+        if in_parentheses {
+            in_parentheses = !town.contains('）');
+            continue;
+        }
+        in_parentheses = town.contains('（') && !town.contains('）');
+        for record in ken_all_records(&record) {
+            process(&record);
+        }
+    }
+    Ok(())
+}
+
+/// 日本郵便の郵便番号データ(KEN_ALL.CSV)の読み込み
+/// 地名として扱うので、-p/--placesを指定した場合だけ出力する。
+pub struct KenAllProcessor;
+impl DictionaryProcessor for KenAllProcessor {
+    fn should_skip(
+        &self,
+        _dict_values: &mut DictValues,
+        record: &StringRecord,
+        _args: &Config,
+    ) -> bool {
+        should_skip_common(_dict_values, record, _args, DictType::KenAll)
+    }
+
+    fn word_class_analyze(
+        &self,
+        _dict_values: &mut DictValues,
+        record: &StringRecord,
+        _args: &Config,
+    ) -> bool {
+        word_class_analyze_common(_dict_values, record, _args, DictType::KenAll)
+    }
+
+    fn read_records(
+        &self,
+        _args: &Config,
+        process: &mut dyn FnMut(&StringRecord),
+    ) -> Option<io::Result<()>> {
+        Some(for_each_input(_args, |path| {
+            read_ken_all(path, _args, process)
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(csv: &str) -> Vec<(String, String)> {
+        let mut records = Vec::new();
+        parse_ken_all(csv.as_bytes(), &mut |record| {
+            assert_eq!(
+                record.iter().skip(3).take(2).collect::<Vec<_>>(),
+                ["名詞", "固有名詞"]
+            );
+            records.push((record[0].to_string(), record[1].to_string()));
+        })
+        .unwrap();
+        records
+    }
+
+    fn pair(pronunciation: &str, notation: &str) -> (String, String) {
+        (pronunciation.to_string(), notation.to_string())
+    }
+
+    #[test]
+    fn address_and_postal_code() {
+        let csv = "01101,\"060  \",\"0600000\",\"ﾎｯｶｲﾄﾞｳ\",\"ｻｯﾎﾟﾛｼﾁｭｳｵｳｸ\",\"ｲｶﾆｹｲｻｲｶﾞﾅｲﾊﾞｱｲ\",\"北海道\",\"札幌市中央区\",\"以下に掲載がない場合\",0,0,0,0,0,0\n\
+            01101,\"064  \",\"0640941\",\"ﾎｯｶｲﾄﾞｳ\",\"ｻｯﾎﾟﾛｼﾁｭｳｵｳｸ\",\"ｱｻﾋｶﾞｵｶ\",\"北海道\",\"札幌市中央区\",\"旭ケ丘\",0,0,1,0,0,0\n";
+        assert_eq!(
+            parse(csv),
+            [
+                pair("ほっかいどう", "北海道"),
+                pair("さっぽろしちゅうおうく", "札幌市中央区"),
+                pair("0600000", "北海道札幌市中央区"),
+                pair("ほっかいどう", "北海道"),
+                pair("さっぽろしちゅうおうく", "札幌市中央区"),
+                pair("あさひがおか", "旭ケ丘"),
+                pair("0640941", "北海道札幌市中央区旭ケ丘"),
+            ]
+        );
+    }
+
+    #[test]
+    fn multi_line_parentheses() {
+        // 括弧書きが3行に分かれた町域名は、最初の行だけを括弧の前まで使う。
+        let csv = "01224,\"066  \",\"0660005\",\"ﾎｯｶｲﾄﾞｳ\",\"ﾁﾄｾｼ\",\"ｲｽﾞﾐｻﾜ(1-3ﾁｮｳﾒ\",\"北海道\",\"千歳市\",\"泉沢（１～３丁目、\",1,0,1,0,0,0\n\
+            01224,\"066  \",\"0660005\",\"ﾎｯｶｲﾄﾞｳ\",\"ﾁﾄｾｼ\",\"ｲｽﾞﾐｻﾜ\",\"北海道\",\"千歳市\",\"５丁目、\",1,0,1,0,0,0\n\
+            01224,\"066  \",\"0660005\",\"ﾎｯｶｲﾄﾞｳ\",\"ﾁﾄｾｼ\",\"ｲｽﾞﾐｻﾜ\",\"北海道\",\"千歳市\",\"７丁目）\",1,0,1,0,0,0\n\
+            01224,\"066  \",\"0660006\",\"ﾎｯｶｲﾄﾞｳ\",\"ﾁﾄｾｼ\",\"ｼﾝﾄﾐ\",\"北海道\",\"千歳市\",\"新富\",0,0,1,0,0,0\n";
+        let records = parse(csv);
+        let notations: Vec<&str> = records.iter().map(|(_, n)| n.as_str()).collect();
+        assert_eq!(
+            notations,
+            [
+                "北海道",
+                "千歳市",
+                "泉沢",
+                "北海道千歳市泉沢",
+                "北海道",
+                "千歳市",
+                "新富",
+                "北海道千歳市新富",
+            ]
+        );
+        assert_eq!(records[2], pair("いずみさわ", "泉沢"));
+    }
+
+    #[test]
+    fn town_names() {
+        assert_eq!(town_name("大通西（１～１９丁目）", '（'), "大通西");
+        assert_eq!(town_name("猿払村一円", '（'), "");
+        assert_eq!(town_name("一円", '（'), "一円");
+        assert_eq!(town_name("琴似の次に番地がくる場合", '（'), "");
+    }
+}
@@ -8,7 +8,7 @@ use lazy_regex::Lazy;
 use lazy_regex::Regex;
 use lazy_regex::regex;
 use lazy_regex::regex_replace_all;
-use std::io::{self, BufWriter, Write};
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
 use std::path::{Path, PathBuf};
 
 use csv::StringRecord;
@@ -17,11 +17,29 @@ use csv::{Error as CsvError, ReaderBuilder};
 use kanaria::string::{ConvertType, UCSStr};
 use kanaria::utils::ConvertTarget;
 
+use encoding_rs::Encoding;
+use encoding_rs::{EUC_JP, SHIFT_JIS, UTF_8, UTF_16BE, UTF_16LE};
+use encoding_rs_io::DecodeReaderBytesBuilder;
+
+use quick_xml::escape::escape;
+
 use crate::utils::adjust_cost;
 use crate::utils::convert_to_hiragana;
+use crate::utils::decode_reader;
+use crate::utils::file_name_match;
+use crate::utils::for_each_input;
+use crate::utils::frequency_to_cost;
+use crate::utils::input_files;
+use crate::utils::open_input;
+use crate::utils::open_text;
+use crate::utils::read_input;
+
+use crate::archive::{open_file, peek_file};
+use crate::output::{OutputFiles, labeled_path};
+use crate::synonyms::{Synonyms, read_synonyms, synonym_groups};
 use crate::utils::unicode_escape_to_char;
 
-use indexmap::IndexMap;
+use indexmap::{IndexMap, IndexSet};
 
 //use std::ops::{Deref, DerefMut};
 
@@ -44,6 +62,49 @@ use foldhash::fast::RandomState;
 //use rustc_hash::FxBuildHasher as RandomState;
 //use ahash::RandomState;
 
+mod aozora;
+mod archive;
+mod atok;
+mod canna;
+mod ime;
+mod jmdict;
+mod json;
+mod kenall;
+mod mecab;
+mod migrate;
+mod mozcdict;
+mod output;
+mod partition;
+mod plist;
+mod shard;
+mod skk;
+mod split;
+mod sudachi;
+mod synonyms;
+mod userdb;
+mod xml;
+
+/// 標準入力から読み込む場合のパス
+pub const STDIN_PATH: &str = "-";
+pub use aozora::AozoraProcessor;
+pub use atok::AtokProcessor;
+pub use canna::CannaProcessor;
+pub use ime::ImeDictProcessor;
+pub use jmdict::{JmdictProcessor, JmnedictProcessor};
+pub use json::JsonProcessor;
+pub use kenall::KenAllProcessor;
+pub use mecab::{MECAB_READINGS, MecabProcessor};
+pub use migrate::migrate_dictionary;
+pub use mozcdict::MozcSystemDictProcessor;
+pub use output::write_output;
+pub use partition::{
+    OTHER_PARTITION, PartitionRule, default_partition_rules, read_partition_rules,
+};
+pub use plist::PlistProcessor;
+pub use shard::{DEFAULT_SHARD_PREFIX, SHARD_SORTS};
+pub use skk::SkkProcessor;
+pub use split::SPLIT_ORDERS;
+
 mod utils {
     use super::*;
 
@@ -81,6 +142,206 @@ mod utils {
         }
     }
 
+    /// 文字コードを推測するために調べる、ファイルの先頭の大きさ
+    pub(crate) const ENCODING_SAMPLE_SIZE: usize = 64 * 1024;
+
+    /// BOMのないUTF-16かどうかを、0のバイトが偶数番目と奇数番目のどちらに多いかで推測する。
+    fn guess_utf16(bytes: &[u8]) -> Option<&'static Encoding> {
+        let sample = &bytes[..bytes.len().min(ENCODING_SAMPLE_SIZE)];
+        let (mut even, mut odd) = (0usize, 0usize);
+        for (i, byte) in sample.iter().enumerate() {
+            if *byte == 0 {
+                if i % 2 == 0 { even += 1 } else { odd += 1 }
+            }
+        }
+        if odd > even * 4 && odd * 20 > sample.len() {
+            Some(UTF_16LE)
+        } else if even > odd * 4 && even * 20 > sample.len() {
+            Some(UTF_16BE)
+        } else {
+            None
+        }
+    }
+
+    /// UTF-8でない場合の文字コードを、Shift_JISとEUC-JPのどちらで読むと
+    /// 仮名と漢字が多くなるかで推測する。
+    fn guess_encoding(bytes: &[u8]) -> &'static Encoding {
+        let mut sample = &bytes[..bytes.len().min(ENCODING_SAMPLE_SIZE)];
+        // 文字の途中で切れないように、最後の改行までにする。
+        if sample.len() < bytes.len()
+            && let Some(end) = sample.iter().rposition(|byte| *byte == b'\n')
+        {
+            sample = &sample[..=end];
+        }
+        let score = |encoding: &'static Encoding| {
+            encoding
+                .decode_without_bom_handling_and_without_replacement(sample)
+                .map(|text| {
+                    text.chars()
+                        .filter(|c| matches!(c, '\u{3040}'..='\u{30FF}' | '\u{4E00}'..='\u{9FFF}'))
+                        .count()
+                })
+        };
+        match (score(SHIFT_JIS), score(EUC_JP)) {
+            (Some(sjis), Some(euc)) if euc > sjis => EUC_JP,
+            (None, Some(_)) => EUC_JP,
+            _ => SHIFT_JIS,
+        }
+    }
+
+    /// 文字コードを判定する。
+    /// BOMがあればBOMの文字コード、文字コードの指定があればその文字コードにする。
+    /// なければUTF-16とUTF-8を確かめ、それ以外はfallbackの文字コード(指定がなければ推測)とみなす。
+    fn detect_encoding(
+        bytes: &[u8],
+        encoding: Option<&'static Encoding>,
+        fallback: Option<&'static Encoding>,
+    ) -> &'static Encoding {
+        if let Some((bom, _)) = Encoding::for_bom(bytes) {
+            return bom;
+        }
+        if let Some(encoding) = encoding.or_else(|| guess_utf16(bytes)) {
+            return encoding;
+        }
+        if std::str::from_utf8(bytes).is_ok() {
+            return UTF_8;
+        }
+        fallback.unwrap_or_else(|| guess_encoding(bytes))
+    }
+
+    /// 文字コードを変換しながら、UTF-8として読む。
+    /// 文字コードは、先頭(ENCODING_SAMPLE_SIZEまで)の内容で判定する。
+    pub(crate) fn decode_reader(
+        mut input: impl Read + 'static,
+        encoding: Option<&'static Encoding>,
+        fallback: Option<&'static Encoding>,
+    ) -> io::Result<Box<dyn Read>> {
+        let mut sample = Vec::new();
+        (&mut input)
+            .take(ENCODING_SAMPLE_SIZE as u64)
+            .read_to_end(&mut sample)?;
+        // 続きがある場合は、文字の途中で切れないように、最後の改行までで判定する。
+        let mut head = sample.as_slice();
+        if sample.len() == ENCODING_SAMPLE_SIZE
+            && let Some(end) = head.iter().rposition(|byte| *byte == b'\n')
+        {
+            head = &head[..=end];
+        }
+        let encoding = detect_encoding(head, encoding, fallback);
+        let reader = DecodeReaderBytesBuilder::new()
+            .encoding(Some(encoding))
+            .strip_bom(true)
+            .build(io::Cursor::new(sample).chain(input));
+        Ok(Box::new(reader))
+    }
+
+    /// 変換元のファイルを開き(圧縮ファイルは展開しながら)、文字コードを変換しながら読む。
+    pub(crate) fn open_text(
+        path: &Path,
+        args: &Config,
+        fallback: Option<&'static Encoding>,
+    ) -> io::Result<Box<dyn Read>> {
+        decode_reader(open_input(path, args)?, args.encoding, fallback)
+    }
+
+    /// 変換元のファイルを開く。圧縮ファイルとzipアーカイブは展開しながら読む。
+    pub(crate) fn open_input(path: &Path, args: &Config) -> io::Result<archive::Input> {
+        archive::open_file(path, args.zip_member.as_deref(), &args.glob, args.debug)
+    }
+
+    /// 変換元のファイルをすべて読み込む。圧縮ファイルとzipアーカイブは展開する。
+    /// 位置を指定して読むバイナリ辞書など、ファイル全体が必要な場合に使う。
+    pub(crate) fn read_input(path: &Path, args: &Config) -> io::Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        open_input(path, args)?.read_to_end(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    /// ファイル名が`*`(任意の文字列)と`?`(任意の1文字)のパターンに合うかどうか
+    /// 大文字と小文字は区別しない。
+    fn glob_match(pattern: &[char], name: &[char]) -> bool {
+        match (pattern.first(), name.first()) {
+            (None, None) => true,
+            (Some('*'), _) => {
+                glob_match(&pattern[1..], name)
+                    || (!name.is_empty() && glob_match(pattern, &name[1..]))
+            }
+            (Some('?'), Some(_)) => glob_match(&pattern[1..], &name[1..]),
+            (Some(p), Some(n)) if p.to_lowercase().eq(n.to_lowercase()) => {
+                glob_match(&pattern[1..], &name[1..])
+            }
+            _ => false,
+        }
+    }
+
+    /// 圧縮ファイルの拡張子
+    const COMPRESSED_EXTENSIONS: [&str; 4] = [".gz", ".bz2", ".xz", ".zst"];
+
+    /// ファイル名がパターンに合うかどうか。圧縮ファイルは、圧縮の拡張子を除いた名前でも比べる。
+    pub(crate) fn file_name_match(pattern: &str, file_name: &str) -> bool {
+        let pattern: Vec<char> = pattern.chars().collect();
+        let matches = |name: &str| glob_match(&pattern, &name.chars().collect::<Vec<_>>());
+        matches(file_name)
+            || COMPRESSED_EXTENSIONS
+                .iter()
+                .filter_map(|extension| file_name.strip_suffix(extension))
+                .any(matches)
+    }
+
+    /// ディレクトリの場合、その中(サブディレクトリも含む)のパターンに合うファイルを名前順に集める。
+    pub(crate) fn collect_files(path: &Path, pattern: &str) -> io::Result<Vec<PathBuf>> {
+        if !path.is_dir() {
+            return Ok(vec![path.to_path_buf()]);
+        }
+        let mut files = Vec::new();
+        for entry in std::fs::read_dir(path)? {
+            let entry_path = entry?.path();
+            if entry_path.is_dir() {
+                files.extend(collect_files(&entry_path, pattern)?);
+            } else if entry_path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| file_name_match(pattern, name))
+            {
+                files.push(entry_path);
+            }
+        }
+        files.sort();
+        Ok(files)
+    }
+
+    /// 変換元のファイルの一覧。ディレクトリはglobのパターンに合うファイルに展開する。
+    pub(crate) fn input_files(args: &Config) -> io::Result<Vec<PathBuf>> {
+        let mut files = Vec::new();
+        for path in &args.csv_files {
+            files.extend(collect_files(path, &args.glob)?);
+        }
+        Ok(files)
+    }
+
+    /// 変換元のファイルを1つずつ読み込む。
+    pub(crate) fn for_each_input(
+        args: &Config,
+        mut read: impl FnMut(&Path) -> io::Result<()>,
+    ) -> io::Result<()> {
+        for path in input_files(args)? {
+            if args.debug > 1 {
+                eprintln!("Reading: {:?}", path);
+            }
+            read(&path)?;
+        }
+        Ok(())
+    }
+
+    /// 出現回数(頻度)からコストを求める。多いものほど低くする。
+    pub(crate) fn frequency_to_cost(frequency: f64) -> i32 {
+        if frequency <= 1.0 {
+            return DEFAULT_COST;
+        }
+        let cost = (DEFAULT_COST as f64 - frequency.ln() * FREQUENCY_WEIGHT) as i32;
+        cost.max(MIN_COST)
+    }
+
     /// 品詞コスト計算
     pub(crate) fn adjust_cost(cost: i32) -> i32 {
         if cost < MIN_COST {
@@ -107,6 +368,10 @@ pub struct DictionaryEntry {
     key: DictionaryKey,
     cost: i32,
     word_class: String,
+    /// ユーザー辞書のコメント
+    comment: String,
+    /// 分類ごとの出力先(partitionsの位置)
+    partition: usize,
 }
 
 /// システム辞書型式とユーザー辞書型式
@@ -144,48 +409,123 @@ impl DictionaryData {
         //None
     }
 
-    /// WIP_output_function_description
-    pub fn output(&self, is_user_dict: bool) -> io::Result<()> {
-        // 非同期の標準出力を取得
-        let mut writer = BufWriter::new(io::stdout());
+    /// 同じ読み、表記、品詞のエントリーがあるかどうか
+    fn contains(&self, key: &DictionaryKey, is_user_dict: bool) -> bool {
+        if is_user_dict {
+            self.user_entries.contains_key(key)
+        } else {
+            self.entries.contains_key(key)
+        }
+    }
 
+    /// WIP_output_function_description
+    pub fn output(&self, writer: &mut dyn Write, is_user_dict: bool) -> io::Result<()> {
         // -Uオプションが設定されている場合のみユーザー辞書を出力
         // ユーザー辞書のエントリーを出力
         if is_user_dict {
-            for entry in self.user_entries.values() {
-                writeln!(
-                    writer,
-                    "{}\t{}\t{}\t",
-                    entry.key.pronunciation, entry.key.notation, entry.word_class
-                )?;
-            }
+            write_user_entries(writer, self.user_entries.values())?;
         } else {
             // システム辞書のエントリーを出力
-            for entry in self.entries.values() {
-                writeln!(
-                    writer,
-                    "{}\t{}\t{}\t{}\t{}",
-                    entry.key.pronunciation,
-                    entry.key.word_class_id,
-                    entry.key.word_class_id,
-                    entry.cost,
-                    entry.key.notation
-                )?;
-            }
+            write_system_entries(writer, self.entries.values())?;
+        }
+        Ok(())
+    }
+
+    /// macOSのユーザー辞書(plist)型式で出力する。
+    /// 品詞の区別はないので、読みと表記が同じものは1つにまとめる。
+    pub fn output_plist(&self, writer: &mut dyn Write, is_user_dict: bool) -> io::Result<()> {
+        let entries = if is_user_dict {
+            &self.user_entries
+        } else {
+            &self.entries
+        };
+        write_plist_entries(writer, entries.values())
+    }
+}
+
+/// システム辞書型式のエントリーを書き込む。
+fn write_system_entries<'a>(
+    writer: &mut dyn Write,
+    entries: impl Iterator<Item = &'a DictionaryEntry>,
+) -> io::Result<()> {
+    for entry in entries {
+        writeln!(
+            writer,
+            "{}\t{}\t{}\t{}\t{}",
+            entry.key.pronunciation,
+            entry.key.word_class_id,
+            entry.key.word_class_id,
+            entry.cost,
+            entry.key.notation
+        )?;
+    }
+    Ok(())
+}
+
+/// ユーザー辞書型式のエントリーを書き込む。
+fn write_user_entries<'a>(
+    writer: &mut dyn Write,
+    entries: impl Iterator<Item = &'a DictionaryEntry>,
+) -> io::Result<()> {
+    for entry in entries {
+        writeln!(
+            writer,
+            "{}\t{}\t{}\t{}",
+            entry.key.pronunciation, entry.key.notation, entry.word_class, entry.comment
+        )?;
+    }
+    Ok(())
+}
+
+/// macOSのユーザー辞書(plist)型式で、エントリーを書き込む。
+fn write_plist_entries<'a>(
+    writer: &mut dyn Write,
+    entries: impl Iterator<Item = &'a DictionaryEntry>,
+) -> io::Result<()> {
+    writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(
+        writer,
+        r#"<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">"#
+    )?;
+    writeln!(writer, r#"<plist version="1.0">"#)?;
+    writeln!(writer, "<array>")?;
+    let mut written = IndexSet::<(&str, &str), RandomState>::default();
+    for entry in entries {
+        if !written.insert((&entry.key.pronunciation, &entry.key.notation)) {
+            continue;
         }
-        // バッファをフラッシュ
-        writer.flush()
+        writeln!(writer, "\t<dict>")?;
+        writeln!(writer, "\t\t<key>phrase</key>")?;
+        writeln!(
+            writer,
+            "\t\t<string>{}</string>",
+            escape(&entry.key.notation)
+        )?;
+        writeln!(writer, "\t\t<key>shortcut</key>")?;
+        writeln!(
+            writer,
+            "\t\t<string>{}</string>",
+            escape(&entry.key.pronunciation)
+        )?;
+        writeln!(writer, "\t</dict>")?;
     }
+    writeln!(writer, "</array>")?;
+    writeln!(writer, "</plist>")?;
+    Ok(())
 }
 
 /// Mozc ソースに含まれるsrc/data/dictionary_oss/id.defを読み込む
 /// 更新される可能性がある。
 type IdDef = IndexMap<String, i32, RandomState>;
 
+/// SudachiDictのCSVの同義語グループ番号の列
+const SUDACHI_SYNONYM_GROUP_INDEX: usize = 18;
 const DEFAULT_COST: i32 = 6000;
 const MIN_COST: i32 = 0;
 const MAX_COST: i32 = 10000;
 const COST_ADJUSTMENT: i32 = 10;
+/// 出現回数の対数あたりに差し引くコスト
+const FREQUENCY_WEIGHT: f64 = 500.0;
 
 fn id_expr(
     clsexpr: &str,
@@ -299,44 +639,60 @@ fn id_expr(
     result_id
 }
 
+/// id.defの品詞文字列を、SudachiDictの品詞に近い書式に揃える。
+fn normalize_id_def_word_class(word_class: &str) -> String {
+    let mut expr = word_class
+        .replace("名詞,一般,*,", "名詞,普通名詞,一般,")
+        .replace("名詞,数,", "名詞,数詞,")
+        .replace("名詞,接尾,助数詞,", "名詞,普通名詞,助数詞可能,")
+        .replace("名詞,サ変接続,*,", "名詞,普通名詞,サ変可能,")
+        .replace("名詞,副詞可能,*,", "名詞,普通名詞,副詞可能,")
+        .replace("動詞,*,", "動詞,一般,")
+        .replace("助動詞,*,", "助動詞,一般,")
+        .replace("副詞,*,", "副詞,一般,")
+        .replace("形容詞,*,", "形容詞,一般,")
+        .replace("感動詞,*,", "感動詞,一般,")
+        .replace("段・", "段,")
+        .replace("形-", "形,")
+        .replace("地域,", "地名,");
+
+    expr = regex_replace_all!(r"カ行([^,]*),", &expr, "カ行,$1").into_owned();
+    expr = regex_replace_all!(r"サ行([^,]*),", &expr, "サ行,$1").into_owned();
+    expr = regex_replace_all!(r"サ変([^,]*),", &expr, "サ変,$1").into_owned();
+    expr = regex_replace_all!(r"ラ行([^,]*),", &expr, "ラ行,$1").into_owned();
+    expr = regex_replace_all!(r"ワ行([^,]*),", &expr, "ワ行,$1").into_owned();
+    expr
+}
+
 /// id.defは更新されうるので、毎回、最新のものを読み込む。
 /// 品詞判定が出来なかった場合、普通名詞とみなす。
 /// _default_noun_idは、その普通名詞のIDを格納しておく。
-fn read_id_def(path: &Path) -> Result<(IdDef, i32), CsvError> {
+fn read_id_def(path: &Path, encoding: Option<&'static Encoding>) -> Result<(IdDef, i32), CsvError> {
+    parse_id_def(decode_reader(
+        open_file(path, None, "*.def", 0)?,
+        encoding,
+        None,
+    )?)
+}
+
+/// `ID 品詞` の行を読み込む。
+fn parse_id_def(input: impl Read) -> Result<(IdDef, i32), CsvError> {
     let mut id_def = IdDef::default();
     let mut reader = ReaderBuilder::new()
         .has_headers(false)
         .delimiter(b' ')
-        .from_path(path)?;
+        .from_reader(input);
     let mut _default_noun_id: i32 = -1;
 
     for result in reader.records() {
         let record = result?;
         let id: i32 = record[0].parse().unwrap();
-        let mut expr = record[1]
-            .replace("名詞,一般,*,", "名詞,普通名詞,一般,")
-            .replace("名詞,数,", "名詞,数詞,")
-            .replace("名詞,接尾,助数詞,", "名詞,普通名詞,助数詞可能,")
-            .replace("名詞,サ変接続,*,", "名詞,普通名詞,サ変可能,")
-            .replace("名詞,副詞可能,*,", "名詞,普通名詞,副詞可能,")
-            .replace("動詞,*,", "動詞,一般,")
-            .replace("助動詞,*,", "助動詞,一般,")
-            .replace("副詞,*,", "副詞,一般,")
-            .replace("形容詞,*,", "形容詞,一般,")
-            .replace("感動詞,*,", "感動詞,一般,")
-            .replace("段・", "段,")
-            .replace("形-", "形,")
-            .replace("地域,", "地名,");
+        let expr = normalize_id_def_word_class(&record[1]);
 
         // 名詞、一般名詞のIDを保存
         if expr == "名詞,普通名詞,一般,*,*,*,*" || expr == "名詞,一般,*,*,*,*,*" {
             _default_noun_id = id;
         }
-        expr = regex_replace_all!(r"カ行([^,]*),", &expr, "カ行,$1").into_owned();
-        expr = regex_replace_all!(r"サ行([^,]*),", &expr, "サ行,$1").into_owned();
-        expr = regex_replace_all!(r"サ変([^,]*),", &expr, "サ変,$1").into_owned();
-        expr = regex_replace_all!(r"ラ行([^,]*),", &expr, "ラ行,$1").into_owned();
-        expr = regex_replace_all!(r"ワ行([^,]*),", &expr, "ワ行,$1").into_owned();
 
         id_def.insert(expr, id);
     }
@@ -404,6 +760,8 @@ fn create_word_class_mapping() -> WordClassMapping {
     mapping.add_mapping("姓", "名詞,固有名詞,人名,姓,*,*,*");
     mapping.add_mapping("動詞一段", "動詞,一般,*,*,一段,*,*");
     mapping.add_mapping("動詞サ変", "動詞,一般,*,*,サ変,*,*");
+    // id.defの `サ変・−ズル,基本形` は、normalize_id_def_word_classで `サ変,・−ズル基本形` になる。
+    mapping.add_mapping("動詞ザ変", "動詞,自立,*,*,サ変,・−ズル基本形,*");
     mapping.add_mapping("動詞カ変", "動詞,一般,*,*,カ変,*,*");
     mapping.add_mapping("動詞ラ変", "動詞,自立,*,*,ラ変,*,*");
     mapping.add_mapping("動詞カ行五段", "動詞,一般,*,*,五段,カ行,*,*");
@@ -638,6 +996,8 @@ pub struct DictValues<'a> {
     notation: &'a mut String,
     word_class_id: &'a mut i32,
     cost: &'a mut i32,
+    comment: &'a mut String,
+    synonyms: &'a Synonyms,
 }
 
 /// WIP_DictionaryProcessor_trait_description
@@ -654,6 +1014,15 @@ pub trait DictionaryProcessor {
         record: &StringRecord,
         _args: &Config,
     ) -> bool;
+    /// CSV以外の型式の辞書は、レコードに変換してprocessへ渡す。
+    /// Noneの場合は、process_dictionaryでCSVとして読み込む。
+    fn read_records(
+        &self,
+        _args: &Config,
+        _process: &mut dyn FnMut(&StringRecord),
+    ) -> Option<io::Result<()>> {
+        None
+    }
 }
 
 // DictType という enum を定義
@@ -664,6 +1033,17 @@ enum DictType {
     Neologd,
     UtDict,
     MozcUserDict,
+    Skk,
+    Canna,
+    Mecab,
+    Atok,
+    Plist,
+    Jmdict,
+    Jmnedict,
+    KenAll,
+    MozcSystemDict,
+    Aozora,
+    Json,
 }
 
 fn should_skip_common(
@@ -676,7 +1056,8 @@ fn should_skip_common(
         Some(p) => p,
         None => return false,
     };
-    if !is_kana(pronunciation) {
+    // 郵便番号データは、郵便番号も読みにする。
+    if !is_kana(pronunciation) && dict_type != DictType::KenAll {
         return true;
     }
 
@@ -704,7 +1085,14 @@ fn should_skip_common(
     }
 
     match dict_type {
-        DictType::Sudachi | DictType::Default => {
+        DictType::Sudachi
+        | DictType::Default
+        | DictType::Skk
+        | DictType::Mecab
+        | DictType::Jmdict
+        | DictType::MozcSystemDict
+        | DictType::Aozora
+        | DictType::Json => {
             if !config.symbols && pronunciation == "キゴウ" && word_class_parts[0].contains("記号")
             {
                 return true;
@@ -771,6 +1159,22 @@ fn should_skip_common(
             false
         }
 
+        DictType::Canna | DictType::Atok => {
+            if !config.symbols && word_class_parts[0] == "記号" {
+                return true;
+            }
+            if word_class_parts.len() > 2 && !config.places && word_class_parts[2] == "地域" {
+                return true;
+            }
+            false
+        }
+
+        DictType::Plist => !config.symbols && is_kigou(notation),
+
+        DictType::Jmnedict => !config.places && word_class_parts.first() == Some(&"地名"),
+
+        DictType::KenAll => !config.places,
+
         DictType::MozcUserDict => {
             // ユーザー辞書の品詞からID.defの品詞文字列へ
             let word_class = u_search_word_class(
@@ -816,7 +1220,14 @@ fn word_class_analyze_common(
         None => return false,
     };
     match dict_type {
-        DictType::Sudachi | DictType::Default => {
+        DictType::Sudachi
+        | DictType::Default
+        | DictType::Skk
+        | DictType::Mecab
+        | DictType::Jmdict
+        | DictType::MozcSystemDict
+        | DictType::Aozora
+        | DictType::Json => {
             *_dict_values.word_class_id = process_word_class(record, config, _dict_values);
             if (!config.places)
                 && search_key(_dict_values.id_def, *_dict_values.word_class_id).contains("地名")
@@ -833,6 +1244,13 @@ fn word_class_analyze_common(
                 return false;
             }
         }
+        DictType::Canna | DictType::Atok | DictType::Jmnedict | DictType::KenAll => {
+            *_dict_values.word_class_id = process_word_class(record, config, _dict_values);
+        }
+        DictType::Plist => {
+            // 品詞の情報がないので、普通名詞とする。
+            *_dict_values.word_class_id = *_dict_values.default_noun_id;
+        }
         DictType::UtDict => {
             let data = &record;
             let word_class = &data[config.word_class_index];
@@ -860,7 +1278,15 @@ fn word_class_analyze_common(
         .get(config.cost_index)
         .map_or(DEFAULT_COST.to_string(), |s| s.to_string());
     let cost = cost_str.parse::<i32>().unwrap_or(DEFAULT_COST);
-    *_dict_values.cost = adjust_cost(cost);
+    // Mozcシステム辞書のコストと、出現回数から求めたコストは、そのまま使う。
+    *_dict_values.cost = if matches!(
+        dict_type,
+        DictType::MozcSystemDict | DictType::Aozora | DictType::Json
+    ) {
+        cost
+    } else {
+        adjust_cost(cost)
+    };
     true
 }
 
@@ -883,12 +1309,17 @@ fn process_word_class(record: &StringRecord, _args: &Config, _dict_values: &mut
     } else if _args.utdict {
         return *_dict_values.default_noun_id;
         //    process_utdict_word_class(&word_class_parts)
-    } else if _args.mozcuserdict {
+    } else if _args.mozcuserdict || _args.ime || _args.jmnedict {
         u_search_word_class(
             _dict_values.mapping,
             _dict_values.id_def,
             process_mozcuserdict_word_class(&word_class_parts),
         )
+    } else if _args.canna || _args.atok || _args.ken_all {
+        normalize_id_def_word_class(&word_class_parts.join(","))
+    } else if _args.mecab || _args.mozcsystemdict {
+        // 読み込み時にid.defの品詞文字列へ変換済み
+        word_class_parts.join(",")
     } else {
         process_sudachi_word_class(&word_class_parts)
     };
@@ -991,6 +1422,24 @@ impl DictionaryProcessor for SudachiProcessor {
     ) -> bool {
         word_class_analyze_common(_dict_values, record, _args, DictType::Sudachi)
     }
+
+    /// バイナリ辞書(system_core.dicなど)の場合は、配布CSVと同じ並びのレコードにする。
+    fn read_records(
+        &self,
+        _args: &Config,
+        process: &mut dyn FnMut(&StringRecord),
+    ) -> Option<io::Result<()>> {
+        // バイナリ辞書と配布CSVが混ざっていてもよい。圧縮されていても、先頭で見分ける。
+        Some(for_each_input(_args, |path| {
+            let bytes = read_input(path, _args)?;
+            if sudachi::is_sudachi_dic(&bytes) {
+                sudachi::read_sudachi_dic(&bytes, process)
+            } else {
+                let input = decode_reader(io::Cursor::new(bytes), _args.encoding, None)?;
+                parse_csv(input, _args, process)
+            }
+        }))
+    }
 }
 
 /// WIP_NeologdProcessor_struct_description
@@ -1079,6 +1528,11 @@ impl DictionaryProcessor for MozcUserDictProcessor {
         };
         *_dict_values.pronunciation = unicode_escape_to_char(&_pronunciation);
         *_dict_values.notation = unicode_escape_to_char(_notation);
+        // コメントは品詞の次の列
+        *_dict_values.comment = record
+            .get(end_index)
+            .map(user_dict_comment)
+            .unwrap_or_default();
         let d: String = search_key(_dict_values.id_def, *_dict_values.word_class_id).to_owned();
         let word_class = _dict_values.class_map.get(&d);
         *_dict_values.word_class_id = match word_class {
@@ -1096,6 +1550,32 @@ impl DictionaryProcessor for MozcUserDictProcessor {
         *_dict_values.cost = adjust_cost(cost);
         true
     }
+
+    fn read_records(
+        &self,
+        _args: &Config,
+        process: &mut dyn FnMut(&StringRecord),
+    ) -> Option<io::Result<()>> {
+        // user_dictionary.dbとテキストが混ざっていてもよい。
+        Some(for_each_input(_args, |path| {
+            let bytes = read_input(path, _args)?;
+            if userdb::is_user_db(&bytes) {
+                userdb::read_user_db(&bytes, _args, process)
+            } else {
+                let input = decode_reader(io::Cursor::new(bytes), _args.encoding, None)?;
+                parse_csv(input, _args, process)
+            }
+        }))
+    }
+}
+
+/// ユーザー辞書のコメント。タブと改行は、出力の列や行が崩れないように空白にする。
+fn user_dict_comment(comment: &str) -> String {
+    comment
+        .trim()
+        .chars()
+        .map(|c| if c.is_control() { ' ' } else { c })
+        .collect()
 }
 
 #[inline]
@@ -1105,6 +1585,22 @@ fn add_dict_data(
     dict_data: &mut DictionaryData,
     _args: &Config,
 ) {
+    // 分類は、ユーザー辞書の品詞名とid.defの品詞から決める。
+    let partition = if _args.partitions.is_empty() {
+        0
+    } else {
+        let user_word_class = u_search_key(
+            _dict_values.mapping,
+            _dict_values.id_def,
+            *_dict_values.word_class_id,
+        )
+        .unwrap_or_else(|| "名詞".to_owned());
+        partition::partition_index(
+            &_args.partitions,
+            &user_word_class,
+            &search_key(_dict_values.id_def, *_dict_values.word_class_id),
+        )
+    };
     if _args.user_dict {
         match u_search_key(
             _dict_values.mapping,
@@ -1121,6 +1617,8 @@ fn add_dict_data(
                         },
                         cost: *_dict_values.cost,
                         word_class: _word_class,
+                        comment: _dict_values.comment.to_owned(),
+                        partition,
                     },
                     true,
                 );
@@ -1135,6 +1633,8 @@ fn add_dict_data(
                         },
                         cost: *_dict_values.cost,
                         word_class: "名詞".to_owned(),
+                        comment: _dict_values.comment.to_owned(),
+                        partition,
                     },
                     true,
                 );
@@ -1150,6 +1650,8 @@ fn add_dict_data(
                 },
                 cost: *_dict_values.cost,
                 word_class: "".to_owned(),
+                comment: _dict_values.comment.to_owned(),
+                partition,
             },
             false,
         );
@@ -1179,11 +1681,61 @@ fn process_record(
     _dict_values: &mut DictValues,
     data: &csv::StringRecord,
 ) {
+    // コメントはユーザー辞書型式の読み込みでだけ設定される。
+    _dict_values.comment.clear();
     if !_processor.should_skip(_dict_values, data, _args)
         && _processor.word_class_analyze(_dict_values, data, _args)
     {
         add_dict_data(data, _dict_values, dict_data, _args);
+        if !_dict_values.synonyms.is_empty() {
+            add_synonym_data(data, _dict_values, dict_data, _args);
+        }
+    }
+}
+
+/// 同義語辞書の別表記を、コストを上げて追加する。
+/// 別表記は、エントリーの同義語グループ番号のグループから引くので、
+/// 同じ表記で読みが違う語(開く: あく、ひらく)には、その読みの語のグループの別表記だけを追加する。
+/// 表記揺れではない別表記は、仮名で読みが同じものだけにする。
+/// 仮名の表記は、表記揺れでも読みが同じものだけにする。
+/// 同じ読み、表記、品詞のエントリーがすでにある場合は追加しない。
+fn add_synonym_data(
+    data: &StringRecord,
+    _dict_values: &mut DictValues,
+    dict_data: &mut DictionaryData,
+    _args: &Config,
+) {
+    let synonyms = _dict_values.synonyms;
+    let notation = _dict_values.notation.to_owned();
+    let variants = data
+        .get(SUDACHI_SYNONYM_GROUP_INDEX)
+        .map(synonym_groups)
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|group| synonyms.get(&(group, notation.to_owned())))
+        .flatten();
+    let cost = *_dict_values.cost;
+    for variant in variants {
+        let surface = &variant.surface;
+        let is_same_reading =
+            is_kana(surface) && convert_to_hiragana(surface) == *_dict_values.pronunciation;
+        if !is_same_reading && (is_kana(surface) || !variant.is_spelling) {
+            continue;
+        }
+        let key = DictionaryKey {
+            pronunciation: _dict_values.pronunciation.to_owned(),
+            notation: surface.to_owned(),
+            word_class_id: *_dict_values.word_class_id,
+        };
+        if dict_data.contains(&key, _args.user_dict) {
+            continue;
+        }
+        *_dict_values.notation = surface.to_owned();
+        *_dict_values.cost = cost.saturating_add(_args.synonym_cost_penalty);
+        add_dict_data(data, _dict_values, dict_data, _args);
     }
+    *_dict_values.notation = notation;
+    *_dict_values.cost = cost;
 }
 
 /// WIP_process_dictionary_function_description
@@ -1192,13 +1744,18 @@ pub fn process_dictionary(
     dict_data: &mut DictionaryData,
     _args: &Config,
 ) -> io::Result<()> {
-    let (mut _id_def, _default_noun_id) = read_id_def(&_args.id_def)?;
+    let (mut _id_def, _default_noun_id) = read_id_def(&_args.id_def, _args.encoding)?;
     let mut class_map = IndexMap::<String, i32, RandomState>::default();
     let mut mapping = create_word_class_mapping();
     let mut pronunciation = String::new();
     let mut notation = String::new();
     let mut word_class_id = -1;
     let mut cost = -1;
+    let mut comment = String::new();
+    let synonyms = match &_args.sudachi_synonyms {
+        Some(path) if _args.sudachi => read_synonyms(path, _args)?,
+        _ => Synonyms::default(),
+    };
 
     let mut _dict_values = DictValues {
         id_def: &mut _id_def,
@@ -1209,6 +1766,8 @@ pub fn process_dictionary(
         notation: &mut notation,
         word_class_id: &mut word_class_id,
         cost: &mut cost,
+        comment: &mut comment,
+        synonyms: &synonyms,
     };
 
     let delimiter_char = parse_delimiter(&_args.delimiter, _args);
@@ -1225,22 +1784,55 @@ pub fn process_dictionary(
         dbg!(&_dict_values);
     }
 
-    let reader = csv::ReaderBuilder::new()
-        .has_headers(false)
-        .delimiter(delimiter_char)
-        .from_path(&_args.csv_file);
+    // 複数のファイルも、同じDictionaryDataに追加して重複を取り除く。
+    let mut process = |record: &StringRecord| {
+        process_record(_processor, dict_data, _args, &mut _dict_values, record)
+    };
+    if let Some(result) = _processor.read_records(_args, &mut process) {
+        return result;
+    }
+    for_each_input(_args, |path| read_csv(path, _args, &mut process))
+}
 
-    for record in reader?.records() {
-        process_record(_processor, dict_data, _args, &mut _dict_values, &record?);
+/// CSV/TSVのファイルを、区切り文字(delimiter)で読み込む。
+pub(crate) fn read_csv(
+    path: &Path,
+    _args: &Config,
+    process: &mut dyn FnMut(&StringRecord),
+) -> io::Result<()> {
+    parse_csv(open_text(path, _args, None)?, _args, process)
+}
+
+/// CSV/TSVのテキストを、レコードにしてprocessへ渡す。
+pub(crate) fn parse_csv(
+    input: impl Read,
+    _args: &Config,
+    process: &mut dyn FnMut(&StringRecord),
+) -> io::Result<()> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(_args.has_header)
+        .delimiter(parse_delimiter(&_args.delimiter, _args))
+        .from_reader(input);
+
+    let header = reader.headers()?.clone();
+    for record in reader.records() {
+        let record = record?;
+        // zipの複数のCSVファイルをつなげた場合、ヘッダー行が繰り返される。
+        if _args.has_header && record == header {
+            continue;
+        }
+        process(&record);
     }
     Ok(())
 }
 
 /// WIP_Config_struct_description
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct Config {
-    /// 変換元のテキストファイルのパス
-    pub csv_file: PathBuf,
+    /// 変換元のテキストファイルのパス(`-` は標準入力、ディレクトリはglobに合うファイル)
+    pub csv_files: Vec<PathBuf>,
+    /// 変換元がディレクトリの場合に読み込むファイル名のパターン
+    pub glob: String,
     /// Mozcソースにあるid.defファイルのパス
     pub id_def: PathBuf,
     /// 読みのフィールド位置。
@@ -1255,16 +1847,90 @@ pub struct Config {
     pub cost_index: usize,
     /// 読み取る変換元のテキストの区切り文字
     pub delimiter: String,
+    /// 変換元のテキストの1行目をヘッダー行(列名)とみなす。
+    pub has_header: bool,
+    /// 変換元のテキストとid.defの文字コード(指定しない場合、BOMと内容から推測する)
+    pub encoding: Option<&'static Encoding>,
+    /// 変換元がzipアーカイブの場合に読み込むファイル(指定しない場合、すべてのCSVファイル)
+    pub zip_member: Option<String>,
     /// 読み取り元をSudachiDictとみなす。
     pub sudachi: bool,
+    /// SudachiDictの同義語辞書(synonyms.txt)のパス。表記揺れの別表記も出力する。
+    pub sudachi_synonyms: Option<PathBuf>,
+    /// 同義語辞書の別表記に加えるコスト
+    pub synonym_cost_penalty: i32,
     /// 読み取り元をUtDictとみなす。
     pub utdict: bool,
     /// 読み取り元をNeologdとみなす。
     pub neologd: bool,
     /// 読み取り元をMozcユーザー辞書型式とみなす。
     pub mozcuserdict: bool,
+    /// 読み取り元をSKK辞書型式とみなす。
+    pub skk: bool,
+    /// 読み取り元をCanna/Anthy(cannadic)辞書型式とみなす。
+    pub canna: bool,
+    /// 読み取り元をMeCab辞書(mecab-ipadic, unidic-mecab)の配布ファイルとみなす。
+    pub mecab: bool,
+    /// MeCab辞書で読みとして使う列(reading, pronunciation, lemma-reading)
+    pub mecab_reading: String,
+    /// 読み取り元をMicrosoft IME / Google日本語入力のユーザー辞書とみなす。
+    pub ime: bool,
+    /// 読み取り元をATOKの単語ファイルとみなす。
+    pub atok: bool,
+    /// 読み取り元をmacOSのユーザー辞書(plist)とみなす。
+    pub plist: bool,
+    /// 読み取り元をJMdictとみなす。
+    pub jmdict: bool,
+    /// 読み取り元をJMnedictとみなす。
+    pub jmnedict: bool,
+    /// 読み取り元を日本郵便の郵便番号データ(KEN_ALL.CSV)とみなす。
+    pub ken_all: bool,
+    /// 読み取り元をMozcシステム辞書型式とみなす。
+    pub mozcsystemdict: bool,
+    /// Mozcシステム辞書型式の品詞IDに対応するid.defファイルのパス(指定しない場合、id_defと同じ)
+    pub source_id_def: Option<PathBuf>,
+    /// Mozcシステム辞書型式の品詞IDを、source_id_defからid_defの品詞IDに書き換える。
+    pub migrate: bool,
+    /// 読み取り元を青空文庫のテキストとみなし、ルビを読み込む。
+    pub aozora: bool,
+    /// 青空文庫のルビに付ける品詞(SudachiDictの品詞の書式)
+    pub aozora_word_class: String,
+    /// 読み取り元をJSON / JSONL型式の単語リストとみなす。
+    pub json: bool,
+    /// JSONの読みのキー名、またはJSON Pointer
+    pub json_pronunciation: String,
+    /// JSONの表記のキー名、またはJSON Pointer
+    pub json_notation: String,
+    /// JSONの品詞のキー名、またはJSON Pointer
+    pub json_word_class: String,
+    /// JSONのコストのキー名、またはJSON Pointer
+    pub json_cost: Option<String>,
+    /// JSONの頻度のキー名、またはJSON Pointer(コストに換算する)
+    pub json_frequency: Option<String>,
+    /// 出力ファイルのパス(指定しない場合、標準出力)
+    pub output: Option<PathBuf>,
     /// 出力する変換型式をMozcユーザー辞書型式にする。
     pub user_dict: bool,
+    /// macOSのユーザー辞書(plist)型式で出力する。
+    pub plist_output: bool,
+    /// Mozcのユーザー辞書ファイル(user_dictionary.db)型式で出力する。
+    pub user_db: bool,
+    /// user_dictionary.dbの辞書名
+    pub user_db_name: String,
+    /// ユーザー辞書のエントリーを、この件数ずつの辞書やファイルに分ける。
+    pub split_entries: Option<usize>,
+    /// 分ける順序(order: 読み込んだ順、pos: 品詞ごと)
+    pub split_order: String,
+    /// 分類ごとのファイルに分ける規則(空の場合は分けない)
+    pub partitions: Vec<PartitionRule>,
+    /// システム辞書型式を、MozcのOSS辞書のようにこの数のファイルに分ける。
+    pub shards: Option<usize>,
+    /// 分けるときの並べ替えの順序(reading, notation, id, cost, order)
+    pub shard_sort: String,
+    /// 分けたファイルの名前の前の部分(`dictionary` なら `dictionary00.txt`)
+    pub shard_prefix: String,
+    /// 前の出力の残りの、番号の付いたファイルを削除する。
+    pub shard_clean: bool,
     /// 出力に地名も含める。
     pub places: bool,
     /// 出力に記号も含める。
@@ -1272,3 +1938,247 @@ pub struct Config {
     /// デバッグ情報の出力。
     pub debug: usize,
 }
+
+/// 列の指定。番号(0から始まる)か、ヘッダー行の列名で指定する。
+#[derive(Debug, Default)]
+pub struct ColumnNames {
+    /// 読みの列
+    pub pronunciation: Option<String>,
+    /// 表記の列
+    pub notation: Option<String>,
+    /// 品詞の開始の列。`pos1..pos6` のような範囲も指定できる。
+    pub word_class: Option<String>,
+    /// 品詞の列数
+    pub word_class_numbers: Option<usize>,
+    /// コストの列
+    pub cost: Option<String>,
+}
+
+impl Config {
+    /// 列の指定を、フィールド位置に置き換える。
+    /// 列名で指定する場合は、has_headerを指定して、ヘッダー行から位置を求める。
+    pub fn resolve_columns(&mut self, columns: &ColumnNames) -> io::Result<()> {
+        let header = if self.has_header {
+            // 列名は、最初のファイルのヘッダー行から求める。
+            let first = input_files(self)?
+                .into_iter()
+                .next()
+                .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no input file"))?;
+            read_header(&first, self, parse_delimiter(&self.delimiter, self))?
+        } else {
+            Vec::new()
+        };
+        if let Some(column) = &columns.pronunciation {
+            self.pronunciation_index = column_index(column, &header, self.has_header)?;
+        }
+        if let Some(column) = &columns.notation {
+            self.notation_index = column_index(column, &header, self.has_header)?;
+        }
+        if let Some(column) = &columns.word_class {
+            let (start, numbers) = column_range(column, &header, self.has_header)?;
+            self.word_class_index = start;
+            if let Some(numbers) = numbers {
+                self.word_class_numbers = numbers;
+            }
+        }
+        if let Some(numbers) = columns.word_class_numbers {
+            self.word_class_numbers = numbers;
+        }
+        if let Some(column) = &columns.cost {
+            self.cost_index = column_index(column, &header, self.has_header)?;
+        }
+        Ok(())
+    }
+}
+
+/// 文字コードの名前(`shift_jis`、`euc-jp`、`utf-16le`など)から、文字コードを求める。
+/// `auto`の場合は、BOMと内容から推測するのでNoneにする。
+pub fn encoding_for_label(label: &str) -> io::Result<Option<&'static Encoding>> {
+    if label.eq_ignore_ascii_case("auto") {
+        return Ok(None);
+    }
+    Encoding::for_label(label.as_bytes())
+        .map(Some)
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("unknown encoding: {}", label),
+            )
+        })
+}
+
+/// ヘッダー行の列名
+fn read_header(path: &Path, args: &Config, delimiter: u8) -> io::Result<Vec<String>> {
+    // 標準入力の場合も、読んだ分はあとでもう一度読める。
+    let input = peek_file(path, args.zip_member.as_deref(), &args.glob, args.debug)?;
+    parse_header(decode_reader(input, args.encoding, None)?, delimiter)
+}
+
+/// 最初のレコードだけを読み、列名にする。
+fn parse_header(input: impl Read, delimiter: u8) -> io::Result<Vec<String>> {
+    let mut reader = ReaderBuilder::new()
+        .has_headers(true)
+        .delimiter(delimiter)
+        .flexible(true)
+        .from_reader(input);
+    Ok(reader
+        .headers()?
+        .iter()
+        .map(|name| name.trim_start_matches('\u{feff}').trim().to_owned())
+        .collect())
+}
+
+/// 番号、またはヘッダー行の列名から、フィールド位置を求める。
+fn column_index(column: &str, header: &[String], has_header: bool) -> io::Result<usize> {
+    if let Ok(index) = column.parse::<usize>() {
+        return Ok(index);
+    }
+    if !has_header {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("column name '{}' requires --header", column),
+        ));
+    }
+    header
+        .iter()
+        .position(|name| name == column)
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "column '{}' not found in the header: {}",
+                    column,
+                    header.join(",")
+                ),
+            )
+        })
+}
+
+/// `pos1..pos6` のような範囲から、開始位置と列数を求める。範囲でなければ開始位置だけ。
+fn column_range(
+    column: &str,
+    header: &[String],
+    has_header: bool,
+) -> io::Result<(usize, Option<usize>)> {
+    let Some((first, last)) = column.split_once("..") else {
+        return Ok((column_index(column, header, has_header)?, None));
+    };
+    let start = column_index(first, header, has_header)?;
+    let end = column_index(last, header, has_header)?;
+    if end < start {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("invalid column range: {}", column),
+        ));
+    }
+    Ok((start, Some(end - start + 1)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 最初の部分のあとは、読むとエラーになる入力
+    struct Truncated;
+    impl Read for Truncated {
+        fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+            Err(io::Error::other("read past the header"))
+        }
+    }
+
+    #[test]
+    fn header_is_the_first_record() {
+        let input = "\u{feff}reading, surface ,pos1,pos2,pos3,cost\n"
+            .as_bytes()
+            .chain(Truncated);
+        let header = parse_header(input, b',').unwrap();
+        assert_eq!(
+            header,
+            ["reading", "surface", "pos1", "pos2", "pos3", "cost"]
+        );
+
+        assert_eq!(column_index("surface", &header, true).unwrap(), 1);
+        assert_eq!(column_index("7", &header, false).unwrap(), 7);
+        assert_eq!(
+            column_index("surface", &header, false).unwrap_err().kind(),
+            io::ErrorKind::InvalidInput
+        );
+        assert!(column_index("lemma", &header, true).is_err());
+        assert_eq!(
+            column_range("pos1..pos3", &header, true).unwrap(),
+            (2, Some(3))
+        );
+        assert_eq!(column_range("cost", &header, true).unwrap(), (5, None));
+        assert!(column_range("pos3..pos1", &header, true).is_err());
+    }
+
+    fn decode(bytes: Vec<u8>, encoding: Option<&'static Encoding>) -> String {
+        let mut text = String::new();
+        decode_reader(io::Cursor::new(bytes), encoding, None)
+            .unwrap()
+            .read_to_string(&mut text)
+            .unwrap();
+        text
+    }
+
+    fn encode(text: &str, encoding: &'static Encoding) -> Vec<u8> {
+        encoding.encode(text).0.into_owned()
+    }
+
+    #[test]
+    fn detected_encodings() {
+        let text = "とうきょう,東京,名詞\n";
+        assert_eq!(decode(encode(text, SHIFT_JIS), None), text);
+        assert_eq!(decode(encode(text, EUC_JP), None), text);
+        assert_eq!(decode(text.as_bytes().to_vec(), None), text);
+
+        // BOMのないUTF-16
+        let utf16le: Vec<u8> = text.encode_utf16().flat_map(u16::to_le_bytes).collect();
+        assert_eq!(decode(utf16le, None), text);
+        let utf16be: Vec<u8> = text.encode_utf16().flat_map(u16::to_be_bytes).collect();
+        assert_eq!(decode(utf16be, None), text);
+
+        // BOMは除き、指定した文字コードよりBOMを優先する。
+        let mut bom = "\u{feff}".as_bytes().to_vec();
+        bom.extend(text.as_bytes());
+        assert_eq!(decode(bom, Some(SHIFT_JIS)), text);
+        assert_eq!(decode(encode(text, EUC_JP), Some(EUC_JP)), text);
+    }
+
+    #[test]
+    fn sample_boundary() {
+        // 判定に使う先頭の部分が文字の途中で切れても、Shift_JISと判定する。
+        let line = "あいうえお,漢字か\n";
+        let text = line.repeat(utils::ENCODING_SAMPLE_SIZE / 10 + 1);
+        let bytes = encode(&text, SHIFT_JIS);
+        assert!(bytes.len() > utils::ENCODING_SAMPLE_SIZE);
+        assert_eq!(decode(bytes, None), text);
+    }
+
+    #[test]
+    fn glob_patterns() {
+        assert!(file_name_match("*.csv", "small_lex.CSV"));
+        assert!(file_name_match("*.csv", "core_lex.csv.gz"));
+        assert!(file_name_match("SKK-JISYO*", "SKK-JISYO.L"));
+        assert!(file_name_match("dictionary??.txt", "dictionary07.txt.zst"));
+        assert!(!file_name_match("dictionary??.txt", "dictionary7.txt"));
+        assert!(!file_name_match("*.csv", "lex.csv.bak"));
+    }
+
+    #[test]
+    fn directory_inputs() {
+        let dir = std::env::temp_dir().join(format!("dict-to-mozc-inputs-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("sub")).unwrap();
+        for name in ["b.csv", "a.txt", "sub/c.csv.gz", "sub/d.tsv"] {
+            std::fs::write(dir.join(name), "").unwrap();
+        }
+        let files = utils::collect_files(&dir, "*.csv");
+        let stdin = utils::collect_files(Path::new(STDIN_PATH), "*.csv");
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(
+            files.unwrap(),
+            [dir.join("b.csv"), dir.join("sub/c.csv.gz")]
+        );
+        assert_eq!(stdin.unwrap(), [PathBuf::from(STDIN_PATH)]);
+    }
+}
@@ -0,0 +1,347 @@
+//! MeCab辞書(mecab-ipadic, unidic-mecab)の配布ファイルの読み込み
+//!
+//! ディレクトリを指定した場合、その中のCSVファイル(Noun.csv, Verb.csv, lex.csvなど)をすべて読み込む。
+//! 列の並びはファイルごとに列数から判定し、読みや表記の列は列名で選ぶ。
+//! レコードは `よみ,表記,コスト,品詞...` の並びに揃え、品詞はid.defの品詞文字列にしておく。
+use super::*;
+
+use encoding_rs::EUC_JP;
+
+/// mecab-ipadicの列名
+const IPADIC_COLUMNS: &[&str] = &[
+    "surface",
+    "left_id",
+    "right_id",
+    "cost",
+    "pos1",
+    "pos2",
+    "pos3",
+    "pos4",
+    "ctype",
+    "cform",
+    "base",
+    "reading",
+    "pronunciation",
+];
+
+/// unidic-mecabの列名
+/// 2.1.2は21列(fFormまで)、2.2.0以降はkanaなどを含む33列。
+const UNIDIC_COLUMNS: &[&str] = &[
+    "surface",
+    "left_id",
+    "right_id",
+    "cost",
+    "pos1",
+    "pos2",
+    "pos3",
+    "pos4",
+    "ctype",
+    "cform",
+    "lform",
+    "lemma",
+    "orth",
+    "pron",
+    "orth_base",
+    "pron_base",
+    "goshu",
+    "itype",
+    "iform",
+    "ftype",
+    "fform",
+    "icontype",
+    "fcontype",
+    "type",
+    "kana",
+    "kana_base",
+    "form",
+    "form_base",
+    "atype",
+    "acontype",
+    "amodtype",
+    "lid",
+    "lemma_id",
+];
+
+/// 品詞を示す列名
+const WORD_CLASS_COLUMNS: [&str; 6] = ["pos1", "pos2", "pos3", "pos4", "ctype", "cform"];
+
+/// 読みとして使う列の種類
+pub const MECAB_READINGS: [&str; 3] = ["reading", "pronunciation", "lemma-reading"];
+
+#[derive(Clone, Copy)]
+enum MecabLayout {
+    Ipadic,
+    Unidic,
+}
+
+impl MecabLayout {
+    /// unidic-mecabは21列以上、それ以外はmecab-ipadicとみなす。
+    fn detect(record: &StringRecord) -> Self {
+        if record.len() >= 21 {
+            MecabLayout::Unidic
+        } else {
+            MecabLayout::Ipadic
+        }
+    }
+
+    fn columns(self) -> &'static [&'static str] {
+        match self {
+            MecabLayout::Ipadic => IPADIC_COLUMNS,
+            MecabLayout::Unidic => UNIDIC_COLUMNS,
+        }
+    }
+
+    fn get<'a>(self, record: &'a StringRecord, name: &str) -> Option<&'a str> {
+        let index = self.columns().iter().position(|c| *c == name)?;
+        record.get(index)
+    }
+
+    /// 読みの種類に対応する列名。先に値があった列を使う。
+    /// mecab-ipadicには語彙素読みがないので、読みを使う。
+    /// unidic-mecab 2.1.2にはkanaの列がないので、活用しない語は語彙素読みを、
+    /// 活用した語(書い)は語彙素読み(カク)が表記に合わないので発音形(カイ)を使う。
+    fn reading_columns(self, reading: &str, inflected: bool) -> &'static [&'static str] {
+        match (self, reading) {
+            (MecabLayout::Ipadic, "pronunciation") => &["pronunciation"],
+            (MecabLayout::Ipadic, _) => &["reading"],
+            (MecabLayout::Unidic, "pronunciation") => &["pron"],
+            (MecabLayout::Unidic, "lemma-reading") => &["lform"],
+            (MecabLayout::Unidic, _) if inflected => &["kana", "pron"],
+            (MecabLayout::Unidic, _) => &["kana", "lform"],
+        }
+    }
+
+    fn notation_column(self) -> &'static str {
+        match self {
+            MecabLayout::Ipadic => "surface",
+            MecabLayout::Unidic => "orth",
+        }
+    }
+
+    /// mecab-ipadicの品詞はid.defと同じ体系なので、id.defと同じように揃える。
+    /// unidic-mecabの品詞はSudachiDictと同じ体系。
+    fn word_class(self, parts: &[&str]) -> String {
+        match self {
+            MecabLayout::Ipadic => {
+                let mut parts = parts.to_vec();
+                parts.resize(7, "*");
+                normalize_id_def_word_class(&parts.join(","))
+            }
+            MecabLayout::Unidic => process_sudachi_word_class(parts),
+        }
+    }
+}
+
+/// MeCabの1行を、`よみ,表記,コスト,品詞...` のレコードに変換する。
+fn mecab_record(layout: MecabLayout, record: &StringRecord, reading: &str) -> Option<StringRecord> {
+    let inflected = layout.get(record, "orth") != layout.get(record, "orth_base");
+    let pronunciation = layout
+        .reading_columns(reading, inflected)
+        .iter()
+        .filter_map(|name| layout.get(record, name))
+        .find(|p| !p.is_empty() && *p != "*")?;
+    let notation = layout.get(record, layout.notation_column())?;
+    let cost = layout.get(record, "cost")?;
+    let parts: Vec<&str> = WORD_CLASS_COLUMNS
+        .iter()
+        .map(|name| layout.get(record, name).unwrap_or("*"))
+        .collect();
+    let word_class = layout.word_class(&parts);
+
+    let mut fields = vec![pronunciation, notation, cost];
+    fields.extend(word_class.split(','));
+    Some(StringRecord::from(fields))
+}
+
+fn read_mecab(args: &Config, process: &mut dyn FnMut(&StringRecord)) -> io::Result<()> {
+    // ディレクトリの場合、その中のCSVファイルをすべて読み込む。
+    for_each_input(args, |path| {
+        // mecab-ipadicはEUC-JPで配布されている。
+        let mut reader = ReaderBuilder::new()
+            .has_headers(false)
+            .flexible(true)
+            .from_reader(open_text(path, args, Some(EUC_JP))?);
+        let mut layout = None;
+        for result in reader.records() {
+            let record = result?;
+            let layout = *layout.get_or_insert_with(|| MecabLayout::detect(&record));
+            if let Some(record) = mecab_record(layout, &record, &args.mecab_reading) {
+                process(&record);
+            }
+        }
+        Ok(())
+    })
+}
+
+/// MeCab辞書(mecab-ipadic, unidic-mecab)の配布ファイルの読み込み
+pub struct MecabProcessor;
+impl DictionaryProcessor for MecabProcessor {
+    fn should_skip(
+        &self,
+        _dict_values: &mut DictValues,
+        record: &StringRecord,
+        _args: &Config,
+    ) -> bool {
+        should_skip_common(_dict_values, record, _args, DictType::Mecab)
+    }
+
+    fn word_class_analyze(
+        &self,
+        _dict_values: &mut DictValues,
+        record: &StringRecord,
+        _args: &Config,
+    ) -> bool {
+        word_class_analyze_common(_dict_values, record, _args, DictType::Mecab)
+    }
+
+    fn read_records(
+        &self,
+        _args: &Config,
+        process: &mut dyn FnMut(&StringRecord),
+    ) -> Option<io::Result<()>> {
+        Some(read_mecab(_args, process))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn csv_record(line: &str) -> StringRecord {
+        StringRecord::from(line.split(',').collect::<Vec<_>>())
+    }
+
+    fn fields(record: &StringRecord) -> Vec<&str> {
+        record.iter().collect()
+    }
+
+    #[test]
+    fn ipadic_layout() {
+        let record =
+            csv_record("書く,772,772,5000,動詞,自立,*,*,五段・カ行イ音便,基本形,書く,カク,カク");
+        let layout = MecabLayout::detect(&record);
+        assert!(matches!(layout, MecabLayout::Ipadic));
+        let converted = mecab_record(layout, &record, "reading").unwrap();
+        assert_eq!(
+            fields(&converted),
+            vec![
+                "カク",
+                "書く",
+                "5000",
+                "動詞",
+                "自立",
+                "*",
+                "*",
+                "五段",
+                "カ行",
+                "イ音便基本形",
+                "*"
+            ]
+        );
+    }
+
+    #[test]
+    fn ipadic_pronunciation() {
+        let record = csv_record(
+            "東京,1293,1293,3003,名詞,固有名詞,地域,一般,*,*,東京,トウキョウ,トーキョー",
+        );
+        let layout = MecabLayout::detect(&record);
+        assert_eq!(
+            &mecab_record(layout, &record, "pronunciation").unwrap()[0],
+            "トーキョー"
+        );
+        // mecab-ipadicには語彙素読みがないので、読みを使う。
+        assert_eq!(
+            &mecab_record(layout, &record, "lemma-reading").unwrap()[0],
+            "トウキョウ"
+        );
+    }
+
+    #[test]
+    fn unidic_layout() {
+        // unidic-mecab 2.1.2の21列
+        let record = csv_record(
+            "猫,5146,5146,5000,名詞,普通名詞,一般,*,*,*,ネコ,猫,猫,ネコ,猫,ネコ,和,*,*,*,*",
+        );
+        let layout = MecabLayout::detect(&record);
+        assert!(matches!(layout, MecabLayout::Unidic));
+        // kanaの列がないので、語彙素読みを使う。
+        let converted = mecab_record(layout, &record, "reading").unwrap();
+        assert_eq!(
+            fields(&converted),
+            vec![
+                "ネコ",
+                "猫",
+                "5000",
+                "名詞",
+                "普通名詞",
+                "一般",
+                "*",
+                "*",
+                "*"
+            ]
+        );
+    }
+
+    #[test]
+    fn unidic_kana_column() {
+        let mut line = vec![
+            "行っ",
+            "1",
+            "1",
+            "4000",
+            "動詞",
+            "非自立可能",
+            "*",
+            "*",
+            "五段-カ行",
+            "連用形-促音便",
+            "イク",
+            "行く",
+            "行っ",
+            "イッ",
+            "行く",
+            "イク",
+            "和",
+            "*",
+            "*",
+            "*",
+            "*",
+            "*",
+            "*",
+            "用",
+            "イッ",
+        ];
+        line.resize(UNIDIC_COLUMNS.len(), "*");
+        let record = StringRecord::from(line);
+        let layout = MecabLayout::detect(&record);
+        let converted = mecab_record(layout, &record, "reading").unwrap();
+        assert_eq!(&converted[0], "イッ");
+        assert_eq!(&converted[1], "行っ");
+        assert_eq!(
+            &mecab_record(layout, &record, "lemma-reading").unwrap()[0],
+            "イク"
+        );
+    }
+
+    #[test]
+    fn unidic_inflected_without_kana() {
+        // unidic-mecab 2.1.2の21列。活用した語は、語彙素読みではなく発音形を使う。
+        let record = csv_record(
+            "書い,1,1,5000,動詞,一般,*,*,五段-カ行,連用形-イ音便,カク,書く,書い,カイ,書く,カク,和,*,*,*,*",
+        );
+        let layout = MecabLayout::detect(&record);
+        let converted = mecab_record(layout, &record, "reading").unwrap();
+        assert_eq!((&converted[0], &converted[1]), ("カイ", "書い"));
+        assert_eq!(
+            &mecab_record(layout, &record, "lemma-reading").unwrap()[0],
+            "カク"
+        );
+    }
+
+    #[test]
+    fn missing_reading() {
+        let record = csv_record("Ｘ,1,1,100,記号,一般,*,*,*,*,Ｘ,*,*");
+        assert!(mecab_record(MecabLayout::Ipadic, &record, "reading").is_none());
+    }
+}
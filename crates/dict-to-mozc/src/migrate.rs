@@ -0,0 +1,155 @@
+//! id.defの版の移行
+//!
+//! Mozcのリリースごとにid.defの品詞IDが変わるので、古いid.defで作られた
+//! Mozcシステム辞書型式(UT辞書など)の品詞IDを、新しいid.defの品詞IDに書き換える。
+//! 品詞文字列が同じIDに対応させ、見つからない場合はid_exprで近い品詞を探す。
+use super::*;
+
+/// 旧id.defの品詞IDから新id.defの品詞IDへの対応表
+struct IdMigration {
+    ids: IndexMap<i32, i32, RandomState>,
+    /// 品詞文字列が一致しなかった旧id.defの品詞ID
+    inexact: IndexSet<i32, RandomState>,
+}
+
+impl IdMigration {
+    fn new(old_id_def: &IdDef, new_id_def: &mut IdDef, default_noun_id: i32) -> Self {
+        let mut class_map = IndexMap::<String, i32, RandomState>::default();
+        let mut ids = IndexMap::<i32, i32, RandomState>::default();
+        let mut inexact = IndexSet::<i32, RandomState>::default();
+        for (word_class, old_id) in old_id_def {
+            let new_id = match new_id_def.get(word_class) {
+                Some(id) => *id,
+                None => {
+                    inexact.insert(*old_id);
+                    id_expr(word_class, new_id_def, &mut class_map, default_noun_id)
+                }
+            };
+            ids.insert(*old_id, new_id);
+        }
+        Self { ids, inexact }
+    }
+}
+
+/// 品詞IDの列を書き換える。
+/// 旧id.defにない品詞IDは、そのまま残す。
+fn migrate_id(
+    field: &mut String,
+    migration: &IdMigration,
+    counts: &mut IndexMap<i32, usize, RandomState>,
+) {
+    let Ok(old_id) = field.parse::<i32>() else {
+        return;
+    };
+    match migration.ids.get(&old_id) {
+        Some(new_id) => {
+            if migration.inexact.contains(&old_id) {
+                *counts.entry(old_id).or_default() += 1;
+            }
+            *field = new_id.to_string();
+        }
+        None => *counts.entry(old_id).or_default() += 1,
+    }
+}
+
+/// Mozcシステム辞書型式の1行の左IDと右IDを書き換える。
+/// 列の足りない行は、そのまま残す。
+fn migrate_line(
+    line: &str,
+    migration: &IdMigration,
+    counts: &mut IndexMap<i32, usize, RandomState>,
+) -> String {
+    let mut fields: Vec<String> = line.split('\t').map(String::from).collect();
+    // よみ、左ID、右ID、コスト、表記
+    if fields.len() >= 5 {
+        for field in &mut fields[1..3] {
+            migrate_id(field, migration, counts);
+        }
+    }
+    fields.join("\t")
+}
+
+/// 旧id.def(source_id_def)で作られたMozcシステム辞書型式の品詞IDを、
+/// id_defの品詞IDに書き換えて出力する。
+/// 品詞文字列が一致しなかった品詞IDは、標準エラー出力に報告する。
+pub fn migrate_dictionary(_args: &Config) -> io::Result<()> {
+    let source_id_def = _args.source_id_def.as_ref().unwrap_or(&_args.id_def);
+    let (old_id_def, _) = read_id_def(source_id_def, _args.encoding)?;
+    let (mut new_id_def, default_noun_id) = read_id_def(&_args.id_def, _args.encoding)?;
+    let migration = IdMigration::new(&old_id_def, &mut new_id_def, default_noun_id);
+
+    let mut counts = IndexMap::<i32, usize, RandomState>::default();
+    write_output(_args.output.as_deref(), |writer| {
+        for_each_input(_args, |path| {
+            for line in BufReader::new(open_text(path, _args, None)?).lines() {
+                writeln!(writer, "{}", migrate_line(&line?, &migration, &mut counts))?;
+            }
+            Ok(())
+        })
+    })?;
+
+    for (old_id, count) in &counts {
+        match migration.ids.get(old_id) {
+            Some(new_id) => eprintln!(
+                "Inexact: {} {} -> {} {} ({} occurrences)",
+                old_id,
+                search_key(&old_id_def, *old_id),
+                new_id,
+                search_key(&new_id_def, *new_id),
+                count
+            ),
+            None => eprintln!("Not found: {} ({} occurrences)", old_id, count),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn id_def(classes: &[(&str, i32)]) -> IdDef {
+        classes
+            .iter()
+            .map(|(class, id)| (class.to_string(), *id))
+            .collect()
+    }
+
+    #[test]
+    fn migrate_ids() {
+        let old_id_def = id_def(&[
+            ("名詞,一般,*,*,*,*,*", 1),
+            ("名詞,固有名詞,人名,姓,*,*,*", 2),
+            ("名詞,固有名詞,人名,名,古い,*,*", 3),
+        ]);
+        let mut new_id_def = id_def(&[
+            ("名詞,固有名詞,人名,名,*,*,*", 10),
+            ("名詞,固有名詞,人名,姓,*,*,*", 11),
+            ("名詞,一般,*,*,*,*,*", 12),
+        ]);
+        let migration = IdMigration::new(&old_id_def, &mut new_id_def, 12);
+        let mut counts = IndexMap::<i32, usize, RandomState>::default();
+
+        assert_eq!(
+            migrate_line("さとう\t2\t2\t5000\t佐藤", &migration, &mut counts),
+            "さとう\t11\t11\t5000\t佐藤"
+        );
+        // 品詞文字列が一致しないものは近い品詞にし、回数を数える。
+        assert_eq!(
+            migrate_line("はなこ\t3\t1\t5000\t花子", &migration, &mut counts),
+            "はなこ\t10\t12\t5000\t花子"
+        );
+        // 旧id.defにないIDと、列の足りない行はそのまま
+        assert_eq!(
+            migrate_line("なにか\t99\t1\t5000\t何か", &migration, &mut counts),
+            "なにか\t99\t12\t5000\t何か"
+        );
+        assert_eq!(
+            migrate_line("# comment\t1", &migration, &mut counts),
+            "# comment\t1"
+        );
+        assert_eq!(counts.get(&3), Some(&1));
+        assert_eq!(counts.get(&99), Some(&1));
+        assert_eq!(counts.get(&1), None);
+    }
+}
@@ -0,0 +1,129 @@
+//! Mozcシステム辞書型式(dictionary00.txtなど)の読み込み
+//!
+//! `よみ\t左ID\t右ID\tコスト\t表記` の並びで、品詞IDは元のid.defで品詞文字列に戻す。
+//! 出力先のid.defで品詞IDを求め直すので、別のid.defに合わせて出力し直すこともできる。
+//! 左IDと右IDが異なる場合は、左IDの品詞とする。
+//! レコードは `よみ,表記,コスト,品詞...` の並びになる。
+use super::*;
+
+/// Mozcシステム辞書型式の1行を、`よみ,表記,コスト,品詞...` のレコードに変換する。
+fn mozc_system_dict_record(
+    record: &StringRecord,
+    id_def: &IdDef,
+    word_classes: &mut IndexMap<i32, String, RandomState>,
+) -> Option<StringRecord> {
+    let pronunciation = record.get(0)?;
+    let left_id: i32 = record.get(1)?.parse().ok()?;
+    let cost = record.get(3)?;
+    let notation = record.get(4)?;
+    let word_class = word_classes
+        .entry(left_id)
+        .or_insert_with(|| search_key(id_def, left_id));
+    if word_class.is_empty() {
+        return None;
+    }
+    let mut fields = vec![pronunciation, notation, cost];
+    fields.extend(word_class.split(','));
+    Some(StringRecord::from(fields))
+}
+
+fn read_mozc_system_dict(args: &Config, process: &mut dyn FnMut(&StringRecord)) -> io::Result<()> {
+    // 元のid.defの指定がなければ、出力先と同じid.defとみなす。
+    let source_id_def = args.source_id_def.as_ref().unwrap_or(&args.id_def);
+    let (id_def, _) = read_id_def(source_id_def, args.encoding)?;
+    let mut word_classes = IndexMap::<i32, String, RandomState>::default();
+
+    for_each_input(args, |path| {
+        // 表記に`"`が含まれるので、引用符は扱わない。
+        let mut reader = ReaderBuilder::new()
+            .has_headers(false)
+            .delimiter(b'\t')
+            .quoting(false)
+            .flexible(true)
+            .from_reader(open_text(path, args, None)?);
+        for result in reader.records() {
+            let record = result?;
+            match mozc_system_dict_record(&record, &id_def, &mut word_classes) {
+                Some(record) => process(&record),
+                None => {
+                    if args.debug > 1 {
+                        eprintln!("Unknown word class id: {:?}", record);
+                    }
+                }
+            }
+        }
+        Ok(())
+    })
+}
+
+/// Mozcシステム辞書型式の読み込み
+/// コストは調整せず、そのまま使う。
+pub struct MozcSystemDictProcessor;
+impl DictionaryProcessor for MozcSystemDictProcessor {
+    fn should_skip(
+        &self,
+        _dict_values: &mut DictValues,
+        record: &StringRecord,
+        _args: &Config,
+    ) -> bool {
+        should_skip_common(_dict_values, record, _args, DictType::MozcSystemDict)
+    }
+
+    fn word_class_analyze(
+        &self,
+        _dict_values: &mut DictValues,
+        record: &StringRecord,
+        _args: &Config,
+    ) -> bool {
+        word_class_analyze_common(_dict_values, record, _args, DictType::MozcSystemDict)
+    }
+
+    fn read_records(
+        &self,
+        _args: &Config,
+        process: &mut dyn FnMut(&StringRecord),
+    ) -> Option<io::Result<()>> {
+        Some(read_mozc_system_dict(_args, process))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn record_from_left_id() {
+        let id_def: IdDef = [
+            ("名詞,一般,*,*,*,*,*".to_string(), 1),
+            ("名詞,固有名詞,地域,一般,*,*,*".to_string(), 2),
+        ]
+        .into_iter()
+        .collect();
+        let mut word_classes = IndexMap::<i32, String, RandomState>::default();
+
+        // 左IDと右IDが異なる場合は、左IDの品詞
+        let line = StringRecord::from(vec!["とうきょう", "2", "1", "3000", "\"東京\""]);
+        let record = mozc_system_dict_record(&line, &id_def, &mut word_classes).unwrap();
+        assert_eq!(
+            record.iter().collect::<Vec<_>>(),
+            [
+                "とうきょう",
+                "\"東京\"",
+                "3000",
+                "名詞",
+                "固有名詞",
+                "地域",
+                "一般",
+                "*",
+                "*",
+                "*"
+            ]
+        );
+
+        // id.defにない品詞IDと、列の足りない行は読まない。
+        let line = StringRecord::from(vec!["なにか", "9", "9", "3000", "何か"]);
+        assert!(mozc_system_dict_record(&line, &id_def, &mut word_classes).is_none());
+        let line = StringRecord::from(vec!["なにか", "1", "1", "3000"]);
+        assert!(mozc_system_dict_record(&line, &id_def, &mut word_classes).is_none());
+    }
+}
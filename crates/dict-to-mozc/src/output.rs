@@ -0,0 +1,182 @@
+//! 変換結果の出力先
+//!
+//! 出力ファイルの指定がなければ標準出力に書き込む。
+//! 指定がある場合は、同じディレクトリの一時ファイルに書き込み、すべて書き込めたら名前を変える。
+//! 途中で失敗した場合は一時ファイルを削除するので、書きかけのファイルは残らない。
+//! 分割して複数のファイルに出力する場合も、すべて書き込めてから名前を変える。
+use super::*;
+
+use std::fs::{self, File};
+
+/// 出力ファイルと同じディレクトリの一時ファイルのパス
+fn temp_path(path: &Path) -> io::Result<PathBuf> {
+    let file_name = path.file_name().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("invalid output file: {:?}", path),
+        )
+    })?;
+    let mut temp_name = std::ffi::OsString::from(".");
+    temp_name.push(file_name);
+    temp_name.push(format!(".{}.tmp", std::process::id()));
+    Ok(path.with_file_name(temp_name))
+}
+
+/// 一時ファイルに書き込んで同期し、一時ファイルのパスを返す。失敗した場合は一時ファイルを削除する。
+fn write_temp(
+    path: &Path,
+    write: impl FnOnce(&mut dyn Write) -> io::Result<()>,
+) -> io::Result<PathBuf> {
+    let temp = temp_path(path)?;
+    let mut writer = BufWriter::new(File::create_new(&temp)?);
+    let result = write(&mut writer)
+        .and_then(|()| writer.into_inner().map_err(|e| e.into_error()))
+        .and_then(|file| file.sync_all());
+    match result {
+        Ok(()) => Ok(temp),
+        Err(e) => {
+            let _ = fs::remove_file(&temp);
+            Err(e)
+        }
+    }
+}
+
+/// 一時ファイルに書き込み、同期してから出力ファイルの名前に変える。
+fn write_file(path: &Path, write: impl FnOnce(&mut dyn Write) -> io::Result<()>) -> io::Result<()> {
+    let temp = write_temp(path, write)?;
+    let result = fs::rename(&temp, path);
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    result
+}
+
+/// 複数のファイルへの出力。すべて一時ファイルに書き込めてから、まとめて名前を変える。
+/// 途中で失敗した場合は一時ファイルを削除するので、前の出力はそのまま残る。
+pub(crate) struct OutputFiles {
+    /// 一時ファイルと出力ファイルのパス
+    files: Vec<(PathBuf, PathBuf)>,
+}
+
+impl OutputFiles {
+    pub(crate) fn new() -> Self {
+        OutputFiles { files: Vec::new() }
+    }
+
+    pub(crate) fn write(
+        &mut self,
+        path: PathBuf,
+        write: impl FnOnce(&mut dyn Write) -> io::Result<()>,
+    ) -> io::Result<()> {
+        let temp = write_temp(&path, write)?;
+        self.files.push((temp, path));
+        Ok(())
+    }
+
+    /// 出力ファイルの名前に変え、dirにある前の出力の残りを削除する。
+    /// 前の出力は、is_outputに合うファイル名のうち、今回書き込まなかったもの。
+    /// (件数が減って `user_dict-05.txt` が要らなくなった場合など)
+    pub(crate) fn commit(mut self, dir: &Path, is_output: impl Fn(&str) -> bool) -> io::Result<()> {
+        let files = std::mem::take(&mut self.files);
+        let mut written = Vec::new();
+        for (i, (temp, path)) in files.iter().enumerate() {
+            if let Err(e) = fs::rename(temp, path) {
+                self.files = files[i..].to_vec();
+                return Err(e);
+            }
+            written.push(path.file_name());
+        }
+        let dir = if dir.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            dir
+        };
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            let file_name = entry.file_name();
+            let is_stale = file_name.to_str().is_some_and(&is_output)
+                && !written.contains(&Some(file_name.as_os_str()))
+                && entry.file_type()?.is_file();
+            if is_stale {
+                fs::remove_file(entry.path())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Drop for OutputFiles {
+    fn drop(&mut self) {
+        for (temp, _) in &self.files {
+            let _ = fs::remove_file(temp);
+        }
+    }
+}
+
+/// ファイル名の拡張子の前に、名前を付ける(`dict.txt` は `dict-名前.txt`)。
+pub(crate) fn labeled_path(path: &Path, label: &str) -> PathBuf {
+    let mut file_name = path.file_stem().unwrap_or_default().to_owned();
+    file_name.push("-");
+    file_name.push(label);
+    if let Some(extension) = path.extension() {
+        file_name.push(".");
+        file_name.push(extension);
+    }
+    path.with_file_name(file_name)
+}
+
+/// 出力ファイル(Noneの場合は標準出力)に書き込む。
+pub fn write_output(
+    output: Option<&Path>,
+    write: impl FnOnce(&mut dyn Write) -> io::Result<()>,
+) -> io::Result<()> {
+    match output {
+        Some(path) => write_file(path, write),
+        None => {
+            let mut writer = BufWriter::new(io::stdout().lock());
+            write(&mut writer)?;
+            writer.flush()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn labeled_paths() {
+        assert_eq!(
+            labeled_path(Path::new("out/user_dict.txt"), "01"),
+            Path::new("out/user_dict-01.txt")
+        );
+        assert_eq!(
+            labeled_path(Path::new("dict.tar.txt"), "人名"),
+            Path::new("dict.tar-人名.txt")
+        );
+        assert_eq!(labeled_path(Path::new("dict"), "02"), Path::new("dict-02"));
+    }
+
+    #[test]
+    fn atomic_write() {
+        let dir = std::env::temp_dir().join(format!("dict-to-mozc-output-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("dict.txt");
+        let written = write_output(Some(&path), |writer| writer.write_all(b"first\n"));
+        let failed = write_output(Some(&path), |writer| {
+            writer.write_all(b"second\n")?;
+            Err(io::Error::other("failed"))
+        });
+        let text = fs::read_to_string(&path);
+        let names: Vec<_> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(written.is_ok());
+        assert_eq!(failed.unwrap_err().to_string(), "failed");
+        // 失敗した書き込みは、前のファイルを変えず、一時ファイルも残さない。
+        assert_eq!(text.unwrap(), "first\n");
+        assert_eq!(names, ["dict.txt"]);
+    }
+}
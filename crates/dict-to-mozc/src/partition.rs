@@ -0,0 +1,251 @@
+//! 分類ごとのファイルへの出力
+//!
+//! 人名、地名、組織、記号、一般の語彙などに分けて、配布物ごとに選べるようにする。
+//! 規則は `名前<TAB>品詞` の行で、品詞はユーザー辞書の品詞名(`人名`)か、
+//! id.defの品詞の先頭の部分(`名詞,固有名詞,人名`)にする。`*` はすべての品詞に合う。
+//! 上の行から順に調べ、最初に合った規則の名前のファイルに出力する。どの規則にも合わないものは「その他」にする。
+//! 名前はファイル名に使うので、パスの区切りや制御文字を含む名前、`.` と `..` は使えない。
+use super::*;
+
+/// どの規則にも合わないエントリーの出力先
+pub const OTHER_PARTITION: &str = "その他";
+
+/// 分類の規則。同じ名前の規則が複数あってもよい。
+#[derive(Debug, Clone)]
+pub struct PartitionRule {
+    /// 出力ファイルに付ける名前
+    pub name: String,
+    /// ユーザー辞書の品詞名、id.defの品詞の先頭の部分、または `*`
+    pub pattern: String,
+}
+
+/// 標準の規則。create_word_class_mappingのユーザー辞書の品詞名で分ける。
+const DEFAULT_PARTITION_RULES: [(&str, &str); 9] = [
+    ("人名", "人名"),
+    ("人名", "姓"),
+    ("人名", "名"),
+    ("人名", "接尾人名"),
+    ("地名", "地名"),
+    ("地名", "接尾地名"),
+    ("組織", "組織"),
+    ("記号", "記号"),
+    ("一般", "*"),
+];
+
+pub fn default_partition_rules() -> Vec<PartitionRule> {
+    DEFAULT_PARTITION_RULES
+        .iter()
+        .map(|(name, pattern)| PartitionRule {
+            name: name.to_string(),
+            pattern: pattern.to_string(),
+        })
+        .collect()
+}
+
+/// ファイル名に付けられる名前かどうか
+pub(crate) fn is_valid_name(name: &str) -> bool {
+    name != "."
+        && name != ".."
+        && !name
+            .chars()
+            .any(|c| c == '/' || c == '\\' || c.is_control())
+}
+
+/// 規則の行を読む。空行と `#` から始まる行は読み飛ばす。
+fn parse_partition_rules(text: &str) -> io::Result<Vec<PartitionRule>> {
+    let mut rules = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let message = match line.split_once('\t') {
+            Some((name, pattern)) if !name.trim().is_empty() && !pattern.trim().is_empty() => {
+                let name = name.trim();
+                if is_valid_name(name) {
+                    rules.push(PartitionRule {
+                        name: name.to_owned(),
+                        pattern: pattern.trim().to_owned(),
+                    });
+                    continue;
+                }
+                format!("invalid partition name at line {}: {:?}", number + 1, name)
+            }
+            _ => format!("invalid partition rule at line {}: {}", number + 1, line),
+        };
+        return Err(io::Error::new(io::ErrorKind::InvalidData, message));
+    }
+    Ok(rules)
+}
+
+/// 規則のファイルを読み込む。
+pub fn read_partition_rules(path: &Path) -> io::Result<Vec<PartitionRule>> {
+    let mut text = String::new();
+    decode_reader(open_file(path, None, "*", 0)?, None, None)?.read_to_string(&mut text)?;
+    let rules = parse_partition_rules(&text)?;
+    if rules.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("no partition rule in {:?}", path),
+        ));
+    }
+    Ok(rules)
+}
+
+/// 規則の品詞が、ユーザー辞書の品詞名か、id.defの品詞の先頭の部分に合うかどうか
+fn rule_matches(pattern: &str, user_word_class: &str, id_def_word_class: &str) -> bool {
+    if pattern == "*" || pattern == user_word_class {
+        return true;
+    }
+    let mut word_class = id_def_word_class.split(',');
+    pattern
+        .split(',')
+        .all(|part| word_class.next() == Some(part))
+}
+
+/// どの規則にも合わないエントリーの出力先の位置。
+/// 「その他」という名前の規則があればその位置に、なければ規則の数にする。
+fn other_index(rules: &[PartitionRule]) -> usize {
+    rules
+        .iter()
+        .position(|rule| rule.name == OTHER_PARTITION)
+        .unwrap_or(rules.len())
+}
+
+/// エントリーの出力先。同じ名前の最初の規則の位置で表す。
+pub(crate) fn partition_index(
+    rules: &[PartitionRule],
+    user_word_class: &str,
+    id_def_word_class: &str,
+) -> usize {
+    rules
+        .iter()
+        .find(|rule| rule_matches(&rule.pattern, user_word_class, id_def_word_class))
+        .and_then(|rule| rules.iter().position(|first| first.name == rule.name))
+        .unwrap_or_else(|| other_index(rules))
+}
+
+/// 出力先の位置と名前。規則の順に並べる。
+fn partitions(rules: &[PartitionRule]) -> Vec<(usize, &str)> {
+    let mut partitions: Vec<(usize, &str)> = Vec::new();
+    for (index, rule) in rules.iter().enumerate() {
+        if !partitions.iter().any(|(_, name)| *name == rule.name) {
+            partitions.push((index, &rule.name));
+        }
+    }
+    if other_index(rules) == rules.len() {
+        partitions.push((rules.len(), OTHER_PARTITION));
+    }
+    partitions
+}
+
+impl DictionaryData {
+    /// 分類ごとに、名前の付いたファイルに出力する。
+    /// `dict.txt` は `dict-人名.txt`、`dict-一般.txt` ... になる。
+    /// エントリーのない分類のファイルは作らず、前の出力にあれば削除する。
+    pub fn output_partitions(&self, path: &Path, args: &Config) -> io::Result<()> {
+        let entries = if args.user_dict {
+            &self.user_entries
+        } else {
+            &self.entries
+        };
+        let partitions = partitions(&args.partitions);
+        let mut files = OutputFiles::new();
+        for &(index, name) in &partitions {
+            let part: Vec<&DictionaryEntry> = entries
+                .values()
+                .filter(|entry| entry.partition == index)
+                .collect();
+            if part.is_empty() {
+                continue;
+            }
+            let path = labeled_path(path, name);
+            if args.debug > 1 {
+                eprintln!("Writing: {:?} ({} entries)", path, part.len());
+            }
+            files.write(path, |writer| {
+                if args.user_db {
+                    let name = format!("{}-{}", args.user_db_name, name);
+                    userdb::write_user_db(writer, &name, part.iter().copied())
+                } else if args.plist_output {
+                    write_plist_entries(writer, part.iter().copied())
+                } else if args.user_dict {
+                    write_user_entries(writer, part.iter().copied())
+                } else {
+                    write_system_entries(writer, part.iter().copied())
+                }
+            })?;
+        }
+        files.commit(path.parent().unwrap_or(Path::new(".")), |file_name| {
+            partitions
+                .iter()
+                .any(|(_, name)| labeled_path(path, name).file_name() == Some(file_name.as_ref()))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(text: &str) -> Vec<PartitionRule> {
+        parse_partition_rules(text).unwrap()
+    }
+
+    #[test]
+    fn rule_matching() {
+        let rules = rules(
+            "# 人名と地名\n\
+             人名\t人名\n\
+             地名\t名詞,固有名詞,地名\n\
+             \n\
+             人名\t姓\n\
+             動詞\t動詞\n",
+        );
+        assert_eq!(partition_index(&rules, "姓", "名詞,固有名詞,人名,姓"), 0);
+        assert_eq!(
+            partition_index(&rules, "地名", "名詞,固有名詞,地名,一般"),
+            1
+        );
+        assert_eq!(partition_index(&rules, "動詞一段", "動詞,一般"), 3);
+        assert_eq!(partition_index(&rules, "名詞", "名詞,普通名詞,一般"), 4);
+        assert_eq!(
+            partitions(&rules),
+            [(0, "人名"), (1, "地名"), (3, "動詞"), (4, OTHER_PARTITION)]
+        );
+    }
+
+    #[test]
+    fn other_rule() {
+        // 「その他」の規則があれば、どれにも合わないエントリーもそこにまとめる。
+        let rules = rules("その他\t記号\n人名\t人名\n");
+        assert_eq!(partition_index(&rules, "記号", "記号,一般"), 0);
+        assert_eq!(partition_index(&rules, "名詞", "名詞,普通名詞,一般"), 0);
+        assert_eq!(partitions(&rules), [(0, OTHER_PARTITION), (1, "人名")]);
+        let rules = default_partition_rules();
+        assert_eq!(partition_index(&rules, "名詞", "名詞,普通名詞,一般"), 8);
+        assert_eq!(partitions(&rules).last(), Some(&(9, OTHER_PARTITION)));
+    }
+
+    #[test]
+    fn invalid_rules() {
+        for (text, message) in [
+            (
+                "人名\t人名\n../x\t*\n",
+                "invalid partition name at line 2: \"../x\"",
+            ),
+            ("..\t*\n", "invalid partition name at line 1: \"..\""),
+            ("a\\b\t*\n", "invalid partition name at line 1: \"a\\\\b\""),
+            (
+                "a\u{7}\t*\n",
+                "invalid partition name at line 1: \"a\\u{7}\"",
+            ),
+            ("# 名前\n人名\n", "invalid partition rule at line 2: 人名"),
+        ] {
+            let error = parse_partition_rules(text).unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+            assert_eq!(error.to_string(), message);
+        }
+        assert_eq!(rules(".人名\t人名\n")[0].name, ".人名");
+    }
+}
@@ -0,0 +1,184 @@
+//! macOSのユーザー辞書(ことえり・テキスト置換)のplistの読み込み
+//!
+//! `<array>` の中に、`shortcut`(よみ)と`phrase`(表記)を持つ `<dict>` が並ぶXML型式。
+//! 品詞の情報はないので、すべて普通名詞として扱う。
+//! レコードは `よみ,表記` の並びになる。
+use super::*;
+
+use crate::xml::{push_reference, push_text, xml_error};
+use quick_xml::Reader;
+use quick_xml::events::Event;
+
+fn read_plist(
+    path: &Path,
+    args: &Config,
+    process: &mut dyn FnMut(&StringRecord),
+) -> io::Result<()> {
+    let mut input = BufReader::new(open_input(path, args)?);
+    // バイナリ型式は読めないので、XML型式に変換してから渡す。
+    if io::BufRead::fill_buf(&mut input)?.starts_with(b"bplist") {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "binary plist is not supported (convert it with `plutil -convert xml1`)",
+        ));
+    }
+    parse_plist(input, process)
+}
+
+/// plistのXMLから、`shortcut`と`phrase`の組をレコードにしてprocessへ渡す。
+fn parse_plist(input: impl io::BufRead, process: &mut dyn FnMut(&StringRecord)) -> io::Result<()> {
+    let mut reader = Reader::from_reader(input);
+    let mut buf = Vec::new();
+    let mut key = String::new();
+    let mut text = String::new();
+    let mut in_text = false;
+    let mut shortcut: Option<String> = None;
+    let mut phrase: Option<String> = None;
+    loop {
+        match reader.read_event_into(&mut buf).map_err(xml_error)? {
+            Event::Start(e) => match e.name().as_ref() {
+                b"dict" => {
+                    shortcut = None;
+                    phrase = None;
+                }
+                b"key" | b"string" => {
+                    text.clear();
+                    in_text = true;
+                }
+                _ => {}
+            },
+            Event::Text(e) if in_text => push_text(&mut text, &e)?,
+            Event::CData(e) if in_text => text.push_str(&e.decode().map_err(xml_error)?),
+            Event::GeneralRef(e) if in_text => push_reference(&mut text, &e)?,
+            Event::End(e) => match e.name().as_ref() {
+                b"key" => {
+                    key = std::mem::take(&mut text);
+                    in_text = false;
+                }
+                b"string" => {
+                    match key.as_str() {
+                        "shortcut" => shortcut = Some(std::mem::take(&mut text)),
+                        "phrase" => phrase = Some(std::mem::take(&mut text)),
+                        _ => {}
+                    }
+                    in_text = false;
+                }
+                b"dict" => {
+                    if let (Some(shortcut), Some(phrase)) = (shortcut.take(), phrase.take()) {
+                        process(&StringRecord::from(vec![shortcut, phrase]));
+                    }
+                }
+                _ => {}
+            },
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+    Ok(())
+}
+
+/// macOSのユーザー辞書(plist)の読み込み
+/// 辞書ファイルはXML型式であること。
+pub struct PlistProcessor;
+impl DictionaryProcessor for PlistProcessor {
+    fn should_skip(
+        &self,
+        _dict_values: &mut DictValues,
+        record: &StringRecord,
+        _args: &Config,
+    ) -> bool {
+        should_skip_common(_dict_values, record, _args, DictType::Plist)
+    }
+
+    fn word_class_analyze(
+        &self,
+        _dict_values: &mut DictValues,
+        record: &StringRecord,
+        _args: &Config,
+    ) -> bool {
+        word_class_analyze_common(_dict_values, record, _args, DictType::Plist)
+    }
+
+    fn read_records(
+        &self,
+        _args: &Config,
+        process: &mut dyn FnMut(&StringRecord),
+    ) -> Option<io::Result<()>> {
+        Some(for_each_input(_args, |path| {
+            read_plist(path, _args, process)
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(xml: &str) -> Vec<Vec<String>> {
+        let mut records = Vec::new();
+        parse_plist(xml.as_bytes(), &mut |record| {
+            records.push(record.iter().map(str::to_owned).collect())
+        })
+        .unwrap();
+        records
+    }
+
+    #[test]
+    fn shortcut_and_phrase() {
+        let records = parse(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<plist version="1.0">
+<array>
+	<dict>
+		<key>phrase</key>
+		<string>R&amp;D</string>
+		<key>shortcut</key>
+		<string>あーる&#x3069;</string>
+	</dict>
+	<dict>
+		<key>shortcut</key>
+		<string><![CDATA[えー<b>]]></string>
+		<key>phrase</key>
+		<string>A&lt;B&gt;</string>
+	</dict>
+	<dict>
+		<key>phrase</key>
+		<string>よみがない</string>
+	</dict>
+</array>
+</plist>"#,
+        );
+        assert_eq!(
+            records,
+            vec![vec!["あーるど", "R&D"], vec!["えー<b>", "A<B>"]]
+        );
+    }
+
+    #[test]
+    fn written_plist_is_read_back() {
+        let entry = |pronunciation: &str, notation: &str, word_class_id| DictionaryEntry {
+            key: DictionaryKey {
+                pronunciation: pronunciation.to_owned(),
+                notation: notation.to_owned(),
+                word_class_id,
+            },
+            cost: DEFAULT_COST,
+            word_class: "名詞".to_owned(),
+            comment: String::new(),
+            partition: 0,
+        };
+        let entries = [
+            entry("えいあんどびー", "A&B", 1),
+            entry("えいあんどびー", "A&B", 2),
+            entry("かっこ", "<>", 1),
+        ];
+        let mut xml = Vec::new();
+        write_plist_entries(&mut xml, entries.iter()).unwrap();
+        // 品詞だけが違うエントリーは、1つにまとめる。
+        assert_eq!(
+            parse(std::str::from_utf8(&xml).unwrap()),
+            vec![vec!["えいあんどびー", "A&B"], vec!["かっこ", "<>"]]
+        );
+    }
+}
@@ -0,0 +1,242 @@
+//! MozcのソースのOSS辞書と同じ、分割して並べ替えたシステム辞書型式の出力
+//!
+//! Mozcのsrc/data/dictionary_ossは、読みの順に並べて `dictionary00.txt` ～ `dictionary09.txt` に分けてある。
+//! 同じように並べ替えて分けると、OSS辞書の代わりや隣に置いたときに、差分が見やすくなる。
+//! 分け目は件数がほぼ同じになる位置にし、並べ替えの最初の値(読みなど)が同じエントリーは同じファイルにする。
+//! OSS辞書の隣に置く場合は、ファイル名の `dictionary` をshard_prefixで変える。
+//! 前の出力の残りのファイルは、OSS辞書のファイルを消さないように、shard_cleanの指定がある場合だけ削除する。
+use super::*;
+
+use std::cmp::Ordering;
+
+/// ファイル名の初期値。OSS辞書と同じ `dictionary00.txt` になる。
+pub const DEFAULT_SHARD_PREFIX: &str = "dictionary";
+
+/// 並べ替えの順序。読み、表記、品詞ID、コスト、読み込んだ順(order)。
+pub const SHARD_SORTS: [&str; 5] = ["reading", "notation", "id", "cost", "order"];
+
+/// 並べ替えの比較。最初の値が同じ場合は、ほかの値で順番を決める。
+fn compare_entries(a: &DictionaryEntry, b: &DictionaryEntry, sort: &str) -> Ordering {
+    let reading = a.key.pronunciation.cmp(&b.key.pronunciation);
+    let notation = a.key.notation.cmp(&b.key.notation);
+    let id = a.key.word_class_id.cmp(&b.key.word_class_id);
+    let cost = a.cost.cmp(&b.cost);
+    match sort {
+        "notation" => notation.then(reading).then(id).then(cost),
+        "id" => id.then(reading).then(cost).then(notation),
+        "cost" => cost.then(reading).then(id).then(notation),
+        "order" => Ordering::Equal,
+        _ => reading.then(id).then(cost).then(notation),
+    }
+}
+
+/// 並べ替えの最初の値が同じかどうか。同じものは別のファイルに分けない。
+fn same_group(a: &DictionaryEntry, b: &DictionaryEntry, sort: &str) -> bool {
+    match sort {
+        "notation" => a.key.notation == b.key.notation,
+        "id" => a.key.word_class_id == b.key.word_class_id,
+        "cost" => a.cost == b.cost,
+        "order" => false,
+        _ => a.key.pronunciation == b.key.pronunciation,
+    }
+}
+
+/// output_shardsが出力する名前のファイル(prefixと2桁以上の番号の `.txt`)かどうか
+fn is_shard_file(prefix: &str, file_name: &str) -> bool {
+    file_name
+        .strip_prefix(prefix)
+        .and_then(|name| name.strip_suffix(".txt"))
+        .is_some_and(|number| number.len() >= 2 && number.bytes().all(|byte| byte.is_ascii_digit()))
+}
+
+/// 並べたエントリーを、件数がほぼ同じになるようにshards個に分ける。
+fn split_shards<'a>(
+    entries: &[&'a DictionaryEntry],
+    shards: usize,
+    sort: &str,
+) -> Vec<Vec<&'a DictionaryEntry>> {
+    let shards = shards.max(1);
+    let mut result: Vec<Vec<&DictionaryEntry>> = vec![Vec::new(); shards];
+    let mut shard = 0;
+    for (i, entry) in entries.iter().enumerate() {
+        // 次のファイルに移るのは、目安の件数を超え、最初の値が変わったところ
+        let limit = entries.len() * (shard + 1) / shards;
+        if shard + 1 < shards && i > 0 && i >= limit && !same_group(entries[i - 1], entry, sort) {
+            shard += 1;
+        }
+        result[shard].push(entry);
+    }
+    result
+}
+
+impl DictionaryData {
+    /// システム辞書のエントリーを並べ替えて、ディレクトリのdictionary00.txt ... に分けて出力する。
+    /// エントリーがないファイルも作るので、ファイルの数はいつも同じになる。
+    /// shard_cleanの指定があれば、前にもっと多く分けたときのファイル(`dictionary12.txt` など)を削除する。
+    pub fn output_shards(&self, dir: &Path, args: &Config) -> io::Result<()> {
+        let prefix = &args.shard_prefix;
+        if prefix.is_empty() || !partition::is_valid_name(prefix) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("invalid shard file prefix: {:?}", prefix),
+            ));
+        }
+        let shards = args.shards.unwrap_or(10).max(1);
+        let mut entries: Vec<&DictionaryEntry> = self.entries.values().collect();
+        entries.sort_by(|a, b| compare_entries(a, b, &args.shard_sort));
+
+        std::fs::create_dir_all(dir)?;
+        let width = (shards - 1).to_string().len().max(2);
+        let mut files = OutputFiles::new();
+        for (i, shard) in split_shards(&entries, shards, &args.shard_sort)
+            .into_iter()
+            .enumerate()
+        {
+            let path = dir.join(format!("{}{:0width$}.txt", prefix, i));
+            if args.debug > 1 {
+                eprintln!("Writing: {:?} ({} entries)", path, shard.len());
+            }
+            files.write(path, |writer| {
+                write_system_entries(writer, shard.into_iter())
+            })?;
+        }
+        files.commit(dir, |file_name| {
+            args.shard_clean && is_shard_file(prefix, file_name)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(pronunciation: &str, word_class_id: i32, cost: i32) -> DictionaryEntry {
+        DictionaryEntry {
+            key: DictionaryKey {
+                pronunciation: pronunciation.to_owned(),
+                notation: pronunciation.to_owned(),
+                word_class_id,
+            },
+            cost,
+            word_class: String::new(),
+            comment: String::new(),
+            partition: 0,
+        }
+    }
+
+    fn readings<'a>(shards: &[Vec<&'a DictionaryEntry>]) -> Vec<Vec<&'a str>> {
+        shards
+            .iter()
+            .map(|shard| {
+                shard
+                    .iter()
+                    .map(|entry| entry.key.pronunciation.as_str())
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn sorted_entries() {
+        let entries = [entry("い", 2, 10), entry("あ", 3, 5), entry("あ", 1, 20)];
+        let mut sorted: Vec<&DictionaryEntry> = entries.iter().collect();
+        sorted.sort_by(|a, b| compare_entries(a, b, "reading"));
+        assert_eq!(
+            sorted.iter().map(|e| e.cost).collect::<Vec<_>>(),
+            [20, 5, 10]
+        );
+        sorted.sort_by(|a, b| compare_entries(a, b, "cost"));
+        assert_eq!(
+            sorted.iter().map(|e| e.cost).collect::<Vec<_>>(),
+            [5, 10, 20]
+        );
+    }
+
+    #[test]
+    fn shard_boundaries() {
+        let entries: Vec<DictionaryEntry> = ["あ", "い", "う", "う", "う", "え"]
+            .iter()
+            .map(|reading| entry(reading, 1, 0))
+            .collect();
+        let sorted: Vec<&DictionaryEntry> = entries.iter().collect();
+        // 同じ読みは同じファイルにする。
+        assert_eq!(
+            readings(&split_shards(&sorted, 3, "reading")),
+            [vec!["あ", "い"], vec!["う", "う", "う"], vec!["え"]]
+        );
+        assert_eq!(
+            readings(&split_shards(&sorted, 2, "order")),
+            [vec!["あ", "い", "う"], vec!["う", "う", "え"]]
+        );
+        // エントリーが少なくても、ファイルの数は変えない。
+        assert_eq!(
+            readings(&split_shards(&sorted[..1], 3, "reading")),
+            [vec!["あ"], vec![], vec![]]
+        );
+    }
+
+    #[test]
+    fn shard_file_names() {
+        assert!(is_shard_file("dictionary", "dictionary00.txt"));
+        assert!(is_shard_file("dictionary", "dictionary123.txt"));
+        assert!(!is_shard_file("dictionary", "dictionary0.txt"));
+        assert!(!is_shard_file("dictionary", "dictionary_oss.txt"));
+        assert!(!is_shard_file("dictionary", "dictionary00.txt.bak"));
+        assert!(!is_shard_file("dictionary", "suffix.txt"));
+        assert!(is_shard_file("user", "user07.txt"));
+        assert!(!is_shard_file("user", "dictionary07.txt"));
+    }
+
+    #[test]
+    fn existing_files() {
+        let dir = std::env::temp_dir().join(format!("dict-to-mozc-shards-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        // MozcのOSS辞書のファイル
+        std::fs::write(dir.join("dictionary07.txt"), "oss\n").unwrap();
+        let mut data = DictionaryData::new();
+        data.add(entry("あ", 1, 0), false);
+        let mut args = Config {
+            shards: Some(2),
+            shard_prefix: "user".to_owned(),
+            ..Default::default()
+        };
+        let next_to = data.output_shards(&dir, &args);
+        let files_next_to = file_names(&dir);
+        args.shard_prefix = DEFAULT_SHARD_PREFIX.to_owned();
+        let in_place = data.output_shards(&dir, &args);
+        let oss = std::fs::read_to_string(dir.join("dictionary07.txt"));
+        args.shard_clean = true;
+        let cleaned = data.output_shards(&dir, &args);
+        let files_cleaned = file_names(&dir);
+        args.shard_prefix = "../user".to_owned();
+        let invalid = data.output_shards(&dir, &args);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(next_to.is_ok() && in_place.is_ok() && cleaned.is_ok());
+        assert_eq!(
+            files_next_to,
+            ["dictionary07.txt", "user00.txt", "user01.txt"]
+        );
+        // shard_cleanの指定がなければ、ほかのファイルは残す。
+        assert_eq!(oss.unwrap(), "oss\n");
+        assert_eq!(
+            files_cleaned,
+            [
+                "dictionary00.txt",
+                "dictionary01.txt",
+                "user00.txt",
+                "user01.txt"
+            ]
+        );
+        assert_eq!(invalid.unwrap_err().kind(), io::ErrorKind::InvalidInput);
+    }
+
+    fn file_names(dir: &Path) -> Vec<String> {
+        let mut names: Vec<String> = std::fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        names
+    }
+}
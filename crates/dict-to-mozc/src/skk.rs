@@ -0,0 +1,487 @@
+//! SKK辞書(SKK-JISYO.Lなど)の読み込み
+//!
+//! `よみ /候補1;注釈/候補2/` の1行を、候補ごとのレコードに分割する。
+//! レコードは `よみ,表記,コスト,品詞1,...,品詞6` の並びで、
+//! 品詞はSudachiDictと同じ書式にしておき、品詞判定はSudachiDictと共通にする。
+use super::*;
+
+use std::collections::HashSet;
+
+/// 送りなしの候補の品詞
+const SKK_NOUN: &str = "名詞,普通名詞,一般,*,*,*";
+/// `>てき` のような接尾辞の品詞
+const SKK_SUFFIX: &str = "接尾辞,名詞的,一般,*,*,*";
+/// `あ>` のような接頭辞の品詞
+const SKK_PREFIX: &str = "接頭辞,*,*,*,*,*";
+
+/// 候補の順番ごとに加えるコスト
+const SKK_CANDIDATE_COST_STEP: i32 = 100;
+
+/// 一段動詞の語幹の末尾になる、え段とい段の仮名。行はROW_NAMESと同じ順。
+const E_ROW: &str = "えけげせぜてでねへべぺめれ";
+const I_ROW: &str = "いきぎしじちぢにひびぴみり";
+const ROW_NAMES: [&str; 13] = [
+    "ア", "カ", "ガ", "サ", "ザ", "タ", "ダ", "ナ", "ハ", "バ", "パ", "マ", "ラ",
+];
+
+/// 語幹がすべて漢字1字に含まれる一段動詞の、読みの語幹と表記
+const ICHIDAN_KANJI: [(&str, &str); 11] = [
+    ("み", "見"),
+    ("き", "着"),
+    ("に", "似"),
+    ("に", "煮"),
+    ("ね", "寝"),
+    ("で", "出"),
+    ("え", "得"),
+    ("へ", "経"),
+    ("い", "居"),
+    ("い", "射"),
+    ("ひ", "干"),
+];
+
+/// 送りが `r` の動詞の活用の種類。
+/// 候補が語幹の末尾のえ段かい段の仮名で終わるもの(たべr /食べ/)と、ICHIDAN_KANJIの動詞は一段動詞にする。
+/// それ以外は五段動詞とする。語幹の末尾が漢字に含まれる動詞は、知る(しr /知/)、帰る(かえr /帰/)のように五段動詞が多い。
+/// `かえr /変/` (変える)のように、漢字だけでは一段動詞と分からないものも五段動詞になる。
+fn r_conjugation(stem: &str, candidate: &str) -> String {
+    let Some(last) = stem.chars().last() else {
+        return "五段-ラ行".to_owned();
+    };
+    let is_ichidan = (candidate != stem && candidate.ends_with(last))
+        || ICHIDAN_KANJI.contains(&(stem, candidate));
+    if !is_ichidan {
+        "五段-ラ行".to_owned()
+    } else if let Some(i) = E_ROW.chars().position(|c| c == last) {
+        format!("下一段-{}行", ROW_NAMES[i])
+    } else if let Some(i) = I_ROW.chars().position(|c| c == last) {
+        format!("上一段-{}行", ROW_NAMES[i])
+    } else {
+        "五段-ラ行".to_owned()
+    }
+}
+
+/// 送りありの送り仮名の子音から、終止形の送り仮名と品詞を求める。
+/// SKKの辞書には活用の種類が含まれないため、語幹と子音から推測する。
+/// 動詞の送り仮名にならない子音(`a`、`o`、`p`)は、語幹だけを名詞とする。
+/// 送り `k` は形容詞の連用形(`たかk /高/`)のこともあるが、五段動詞とし、read_skkで見分ける。
+fn okuri_word_class(stem: &str, candidate: &str, okuri: char) -> (&'static str, String) {
+    let verb = |conjugation: &str| format!("動詞,一般,*,*,{},終止形-一般", conjugation);
+    match okuri {
+        'k' => ("く", verb("五段-カ行")),
+        'g' => ("ぐ", verb("五段-ガ行")),
+        's' => ("す", verb("五段-サ行")),
+        't' | 'c' => ("つ", verb("五段-タ行")),
+        'n' => ("ぬ", verb("五段-ナ行")),
+        'b' => ("ぶ", verb("五段-バ行")),
+        'm' => ("む", verb("五段-マ行")),
+        'r' => ("る", verb(&r_conjugation(stem, candidate))),
+        'w' | 'u' => ("う", verb("五段-ワ行")),
+        'e' => ("える", verb("下一段-ア行")),
+        'j' => ("じる", verb("上一段-ザ行")),
+        // SudachiDictの品詞にはザ変がないので、normalize_id_def_word_classで揃えたid.defのザ変の品詞で指定する。
+        'z' => ("ずる", "動詞,自立,*,*,サ変,・−ズル基本形".to_owned()),
+        'h' => ("ふ", verb("文語四段-ハ行")),
+        'd' => ("づ", verb("文語四段-ダ行")),
+        'y' => ("ゆ", verb("文語下二段-ヤ行")),
+        'i' => ("い", "形容詞,一般,*,*,形容詞,終止形-一般".to_owned()),
+        _ => ("", SKK_NOUN.to_owned()),
+    }
+}
+
+/// `(concat "...\057...")` の8進数エスケープを戻す。
+/// それ以外のLisp式の候補は変換できないのでNoneを返す。
+fn decode_concat(candidate: &str) -> Option<String> {
+    let inner = candidate
+        .strip_prefix("(concat \"")?
+        .strip_suffix("\")")?
+        .replace("\" \"", "");
+    let mut result = String::new();
+    let mut chars = inner.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        let mut octal = String::new();
+        while octal.len() < 3 {
+            match chars.peek() {
+                Some(d) if d.is_digit(8) => {
+                    octal.push(*d);
+                    chars.next();
+                }
+                _ => break,
+            }
+        }
+        if octal.is_empty() {
+            result.extend(chars.next());
+        } else {
+            result.push(char::from_u32(u32::from_str_radix(&octal, 8).ok()?)?);
+        }
+    }
+    Some(result)
+}
+
+/// 候補部分 `/候補1;注釈/候補2/` から、注釈を除いた候補を取り出す。
+/// 送りありの `[く/書/]` のようなブロックは読み飛ばす。
+fn split_candidates(candidates: &str) -> Vec<String> {
+    let mut result = Vec::new();
+    let mut in_block = false;
+    for candidate in candidates.trim().trim_matches('/').split('/') {
+        if in_block {
+            in_block = !candidate.ends_with(']');
+            continue;
+        }
+        if candidate.starts_with('[') {
+            in_block = true;
+            continue;
+        }
+        // 注釈を取り除く
+        let candidate = match candidate.split_once(';') {
+            Some((c, _)) => c,
+            None => candidate,
+        };
+        let candidate = if candidate.starts_with('(') {
+            match decode_concat(candidate) {
+                Some(c) => c,
+                None => continue,
+            }
+        } else {
+            candidate.to_owned()
+        };
+        if !candidate.is_empty() && !result.contains(&candidate) {
+            result.push(candidate);
+        }
+    }
+    result
+}
+
+/// 空行と、`;` で始まるコメント行
+fn is_skk_comment(line: &str) -> bool {
+    let line = line.trim_end();
+    line.is_empty() || line.starts_with(';')
+}
+
+/// 送りありの読みの末尾の、送り仮名の子音
+fn okuri_consonant(yomi: &str) -> Option<char> {
+    let mut chars = yomi.chars().rev();
+    match (chars.next(), chars.next()) {
+        (Some(c), Some(s)) if c.is_ascii_lowercase() && !s.is_ascii() => Some(c),
+        _ => None,
+    }
+}
+
+/// SKK辞書の1行を、候補ごとのレコードに変換する。
+fn skk_line_to_records(line: &str) -> Vec<StringRecord> {
+    if is_skk_comment(line) {
+        return Vec::new();
+    }
+    let line = line.trim_end();
+    let (yomi, candidates) = match line.split_once(' ') {
+        Some(v) => v,
+        None => return Vec::new(),
+    };
+
+    // 送りありの場合、読みの末尾は送り仮名の子音になる。
+    let okuri = okuri_consonant(yomi);
+    let stem = match okuri {
+        Some(c) => &yomi[..yomi.len() - c.len_utf8()],
+        None => yomi.trim_start_matches('>').trim_end_matches('>'),
+    };
+    let word_class = |candidate: &str| match okuri {
+        Some(c) => okuri_word_class(stem, candidate, c),
+        None if yomi.starts_with('>') => ("", SKK_SUFFIX.to_owned()),
+        None if yomi.ends_with('>') => ("", SKK_PREFIX.to_owned()),
+        None => ("", SKK_NOUN.to_owned()),
+    };
+
+    split_candidates(candidates)
+        .iter()
+        .enumerate()
+        .map(|(i, candidate)| {
+            let (okurigana, word_class) = word_class(candidate);
+            let pronunciation = format!("{}{}", stem, okurigana);
+            let notation = format!("{}{}", candidate, okurigana);
+            let cost = (DEFAULT_COST + i as i32 * SKK_CANDIDATE_COST_STEP).to_string();
+            let mut fields = vec![pronunciation.as_str(), notation.as_str(), cost.as_str()];
+            fields.extend(word_class.split(','));
+            StringRecord::from(fields)
+        })
+        .collect()
+}
+
+/// 形容詞の語幹の読みと表記。送り `i` の `たかい,高い` と、送り `k` の `たかく,高く` は同じになる。
+fn adjective_stem(record: &StringRecord, okurigana: &str) -> Option<(String, String)> {
+    Some((
+        record.get(0)?.strip_suffix(okurigana)?.to_owned(),
+        record.get(1)?.strip_suffix(okurigana)?.to_owned(),
+    ))
+}
+
+/// 送り `k` の候補のうち、形容詞の連用形ではないものを出力する。
+fn flush_k_records(
+    k_records: &mut Vec<StringRecord>,
+    adjectives: &HashSet<(String, String)>,
+    args: &Config,
+    process: &mut dyn FnMut(&StringRecord),
+) {
+    for record in k_records.drain(..) {
+        match adjective_stem(&record, "く") {
+            Some(stem) if adjectives.contains(&stem) => {
+                if args.debug > 2 {
+                    eprintln!("Skipped adjective: {}", record.get(1).unwrap_or_default());
+                }
+            }
+            _ => process(&record),
+        }
+    }
+}
+
+/// SKK辞書の行を読み、読み込めなかった行の数を返す。
+/// 送り `k` の候補は、同じ語幹と表記の形容詞(`たかi /高/`)があれば形容詞の連用形なので出力しない。
+/// 送りありの行は読みの逆順に並び、`たかk` が `たかi` より前にあるので、
+/// 送り `k` の候補は、送りありの行が終わってから出力する。
+fn parse_skk(
+    input: impl BufRead,
+    args: &Config,
+    process: &mut dyn FnMut(&StringRecord),
+) -> io::Result<usize> {
+    let mut skipped = 0;
+    let mut adjectives = HashSet::new();
+    let mut k_records = Vec::new();
+    for line in input.lines() {
+        let line = line?;
+        let records = skk_line_to_records(&line);
+        if records.is_empty() && !is_skk_comment(&line) {
+            skipped += 1;
+            if args.debug > 2 {
+                eprintln!("Skipped: {}", line);
+            }
+        }
+        let okuri = line
+            .split_once(' ')
+            .and_then(|(yomi, _)| okuri_consonant(yomi));
+        if okuri.is_none() && !records.is_empty() {
+            flush_k_records(&mut k_records, &adjectives, args, process);
+        }
+        for record in records {
+            match okuri {
+                Some('i') => adjectives.extend(adjective_stem(&record, "い")),
+                Some('k') => {
+                    k_records.push(record);
+                    continue;
+                }
+                _ => {}
+            }
+            process(&record);
+        }
+    }
+    flush_k_records(&mut k_records, &adjectives, args, process);
+    Ok(skipped)
+}
+
+/// 読み込めない行(候補がLisp式だけの行など)の数は、debugの指定があれば表示する。
+fn read_skk(path: &Path, args: &Config, process: &mut dyn FnMut(&StringRecord)) -> io::Result<()> {
+    let skipped = parse_skk(BufReader::new(open_text(path, args, None)?), args, process)?;
+    if args.debug > 0 && skipped > 0 {
+        eprintln!("Skipped {} lines in {:?}", skipped, path);
+    }
+    Ok(())
+}
+
+/// SKK辞書型式の読み込み
+/// 文字コードは、指定がなければBOMと内容から推測する。
+pub struct SkkProcessor;
+impl DictionaryProcessor for SkkProcessor {
+    fn should_skip(
+        &self,
+        _dict_values: &mut DictValues,
+        record: &StringRecord,
+        _args: &Config,
+    ) -> bool {
+        should_skip_common(_dict_values, record, _args, DictType::Skk)
+    }
+
+    fn word_class_analyze(
+        &self,
+        _dict_values: &mut DictValues,
+        record: &StringRecord,
+        _args: &Config,
+    ) -> bool {
+        word_class_analyze_common(_dict_values, record, _args, DictType::Skk)
+    }
+
+    fn read_records(
+        &self,
+        _args: &Config,
+        process: &mut dyn FnMut(&StringRecord),
+    ) -> Option<io::Result<()>> {
+        Some(for_each_input(_args, |path| read_skk(path, _args, process)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields(record: &StringRecord) -> Vec<&str> {
+        record.iter().collect()
+    }
+
+    #[test]
+    fn okuri_nasi_candidates() {
+        let records = skk_line_to_records("かんじ /漢字/感じ;feeling/幹事/漢字/");
+        let rows: Vec<Vec<&str>> = records.iter().map(fields).collect();
+        assert_eq!(
+            rows,
+            vec![
+                vec![
+                    "かんじ",
+                    "漢字",
+                    "6000",
+                    "名詞",
+                    "普通名詞",
+                    "一般",
+                    "*",
+                    "*",
+                    "*"
+                ],
+                vec![
+                    "かんじ",
+                    "感じ",
+                    "6100",
+                    "名詞",
+                    "普通名詞",
+                    "一般",
+                    "*",
+                    "*",
+                    "*"
+                ],
+                vec![
+                    "かんじ",
+                    "幹事",
+                    "6200",
+                    "名詞",
+                    "普通名詞",
+                    "一般",
+                    "*",
+                    "*",
+                    "*"
+                ],
+            ]
+        );
+    }
+
+    #[test]
+    fn okuri_ari_conjugation() {
+        let word_class = |line: &str| {
+            let records = skk_line_to_records(line);
+            let record = &records[0];
+            (
+                record[0].to_owned(),
+                record[1].to_owned(),
+                fields(record)[3..].join(","),
+            )
+        };
+        assert_eq!(
+            word_class("かk /書/[く/書/]/"),
+            (
+                "かく".to_owned(),
+                "書く".to_owned(),
+                "動詞,一般,*,*,五段-カ行,終止形-一般".to_owned()
+            )
+        );
+        assert_eq!(
+            word_class("たべr /食べ/"),
+            (
+                "たべる".to_owned(),
+                "食べる".to_owned(),
+                "動詞,一般,*,*,下一段-バ行,終止形-一般".to_owned()
+            )
+        );
+        assert_eq!(
+            word_class("みr /見/").2,
+            "動詞,一般,*,*,上一段-マ行,終止形-一般"
+        );
+        assert_eq!(
+            word_class("とr /取/").2,
+            "動詞,一般,*,*,五段-ラ行,終止形-一般"
+        );
+        assert_eq!(
+            word_class("おきr /起き/").2,
+            "動詞,一般,*,*,上一段-カ行,終止形-一般"
+        );
+        assert_eq!(word_class("かんj /感/").1, "感じる");
+        assert_eq!(word_class("かんz /感/").1, "感ずる");
+        assert_eq!(
+            word_class("たかi /高/").2,
+            "形容詞,一般,*,*,形容詞,終止形-一般"
+        );
+        // 動詞の送り仮名にならない子音は、語幹だけの名詞にする。
+        assert_eq!(
+            word_class("おおo /大/"),
+            ("おお".to_owned(), "大".to_owned(), SKK_NOUN.to_owned())
+        );
+    }
+
+    #[test]
+    fn prefix_suffix_and_concat() {
+        assert_eq!(&skk_line_to_records(">てき /的/")[0][4], "名詞的");
+        assert_eq!(&skk_line_to_records("あ> /亜/")[0][3], "接頭辞");
+        let records = skk_line_to_records(r#"いち /(concat "1\0572")/(skk-foo)/"#);
+        assert_eq!(records.len(), 1);
+        assert_eq!(&records[0][1], "1/2");
+    }
+
+    #[test]
+    fn comments_and_lisp_only_lines() {
+        assert!(is_skk_comment(";; okuri-ari entries."));
+        assert!(is_skk_comment("  "));
+        assert!(skk_line_to_records(";; okuri-ari entries.").is_empty());
+        assert!(skk_line_to_records("ほげ /(skk-foo)/").is_empty());
+        assert!(!is_skk_comment("ほげ /(skk-foo)/"));
+    }
+
+    #[test]
+    fn r_okuri_godan() {
+        // 語幹の末尾がえ段かい段でも、漢字に含まれる動詞は五段動詞にする。
+        for line in [
+            "しr /知/",
+            "はいr /入/",
+            "はしr /走/",
+            "にぎr /握/",
+            "へr /減/",
+            "けr /蹴/",
+            "かえr /帰/",
+        ] {
+            let records = skk_line_to_records(line);
+            assert_eq!(&records[0][7], "五段-ラ行", "{}", line);
+        }
+        let conjugations: Vec<String> = skk_line_to_records("きr /着/切/")
+            .iter()
+            .map(|record| format!("{}{}", &record[1], &record[7]))
+            .collect();
+        assert_eq!(conjugations, ["着る上一段-カ行", "切る五段-ラ行"]);
+        assert_eq!(&skk_line_to_records("ねr /寝/")[0][7], "下一段-ナ行");
+    }
+
+    #[test]
+    fn k_okuri_adjectives() {
+        let text = "かk /書/\nたかk /高/\nたかi /高/\nのr /乗/\nかき /柿/\n";
+        let mut records = Vec::new();
+        let skipped = parse_skk(text.as_bytes(), &Config::default(), &mut |record| {
+            records.push(format!("{},{},{}", &record[0], &record[1], &record[7]))
+        })
+        .unwrap();
+        assert_eq!(skipped, 0);
+        // 高く(形容詞の連用形)は、五段動詞として出力しない。
+        assert_eq!(
+            records,
+            [
+                "たかい,高い,形容詞",
+                "のる,乗る,五段-ラ行",
+                "かく,書く,五段-カ行",
+                "かき,柿,*"
+            ]
+        );
+    }
+}
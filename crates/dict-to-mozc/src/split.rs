@@ -0,0 +1,182 @@
+//! ユーザー辞書の分割出力
+//!
+//! Mozcのユーザー辞書は、件数が多いと取り込みやmozc_serverの応答が止まるので、
+//! ユーザー辞書のエントリーをsplit_entries件ずつに分ける。
+//! 読み込んだ順(order)か、品詞ごと(pos)に分け、どちらも同じ入力からは同じ分け方になる。
+//! user_dictionary.dbは名前の付いた辞書に、それ以外は番号の付いたファイルに分ける。
+use super::*;
+
+/// 分割の順序。読み込んだ順(order)と品詞ごと(pos)。
+pub const SPLIT_ORDERS: [&str; 2] = ["order", "pos"];
+
+/// 分割したエントリーと、辞書名やファイル名に付ける名前
+pub(crate) struct EntryChunk<'a> {
+    pub(crate) label: String,
+    pub(crate) entries: Vec<&'a DictionaryEntry>,
+}
+
+/// 番号の桁数。並べたときに順番どおりになるように、最低2桁でそろえる。
+fn number_width(count: usize) -> usize {
+    count.to_string().len().max(2)
+}
+
+/// エントリーをsize件ずつに分け、prefixと番号の名前を付ける。
+fn push_chunks<'a>(
+    chunks: &mut Vec<EntryChunk<'a>>,
+    prefix: &str,
+    entries: &[&'a DictionaryEntry],
+    size: usize,
+) {
+    let width = number_width(entries.len().div_ceil(size));
+    for (i, entries) in entries.chunks(size).enumerate() {
+        chunks.push(EntryChunk {
+            label: format!("{}{:0width$}", prefix, i + 1),
+            entries: entries.to_vec(),
+        });
+    }
+}
+
+/// output_splitが出力する名前のファイルかどうか。
+/// `user_dict-01.txt` か、品詞ごとの `user_dict-名詞-01.txt` の形の名前にする。
+fn is_split_file(path: &Path, file_name: &str) -> bool {
+    let (Some(stem), extension) = (
+        path.file_stem().and_then(|stem| stem.to_str()),
+        path.extension().map(|extension| extension.to_str()),
+    ) else {
+        return false;
+    };
+    let label = match extension {
+        Some(Some(extension)) => file_name
+            .strip_suffix(extension)
+            .and_then(|name| name.strip_suffix('.')),
+        Some(None) => None,
+        None => Some(file_name),
+    }
+    .and_then(|name| name.strip_prefix(stem))
+    .and_then(|name| name.strip_prefix('-'));
+    let number = match label.map(|label| label.rsplit_once('-')) {
+        Some(Some((pos, number))) if userdb::USER_POS.contains(&pos) => number,
+        Some(Some(_)) | None => return false,
+        Some(None) => label.unwrap_or_default(),
+    };
+    number.len() >= 2 && number.bytes().all(|byte| byte.is_ascii_digit())
+}
+
+impl DictionaryData {
+    /// ユーザー辞書のエントリーを、size件ずつに分ける。
+    /// posの場合は、ユーザー辞書の品詞の番号順に、品詞ごとに分ける。
+    pub(crate) fn split_user_entries(&self, size: usize, order: &str) -> Vec<EntryChunk<'_>> {
+        let size = size.max(1);
+        let mut chunks = Vec::new();
+        if order == "pos" {
+            let mut groups = IndexMap::<&str, Vec<&DictionaryEntry>, RandomState>::default();
+            for entry in self.user_entries.values() {
+                groups.entry(&entry.word_class).or_default().push(entry);
+            }
+            groups
+                .sort_by(|a, _, b, _| userdb::user_pos_number(a).cmp(&userdb::user_pos_number(b)));
+            for (word_class, entries) in &groups {
+                push_chunks(&mut chunks, &format!("{}-", word_class), entries, size);
+            }
+        } else {
+            let entries: Vec<&DictionaryEntry> = self.user_entries.values().collect();
+            push_chunks(&mut chunks, "", &entries, size);
+        }
+        chunks
+    }
+
+    /// ユーザー辞書のエントリーを分けて、番号の付いたファイルに出力する。
+    /// `user_dict.txt` は `user_dict-01.txt`、`user_dict-02.txt` ... になる。
+    /// 前に分けたときの番号の付いたファイルで、今回は要らなくなったものは削除する。
+    pub fn output_split(&self, path: &Path, args: &Config) -> io::Result<()> {
+        let size = args.split_entries.unwrap_or(usize::MAX);
+        let mut files = OutputFiles::new();
+        for chunk in self.split_user_entries(size, &args.split_order) {
+            let path = labeled_path(path, &chunk.label);
+            if args.debug > 1 {
+                eprintln!("Writing: {:?} ({} entries)", path, chunk.entries.len());
+            }
+            files.write(path, |writer| {
+                if args.plist_output {
+                    write_plist_entries(writer, chunk.entries.iter().copied())
+                } else {
+                    write_user_entries(writer, chunk.entries.iter().copied())
+                }
+            })?;
+        }
+        files.commit(path.parent().unwrap_or(Path::new(".")), |file_name| {
+            is_split_file(path, file_name)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn data(word_classes: &[&str]) -> DictionaryData {
+        let mut data = DictionaryData::new();
+        for (i, word_class) in word_classes.iter().enumerate() {
+            data.add(
+                DictionaryEntry {
+                    key: DictionaryKey {
+                        pronunciation: format!("よみ{}", i),
+                        notation: format!("表記{}", i),
+                        word_class_id: 0,
+                    },
+                    cost: 0,
+                    word_class: word_class.to_string(),
+                    comment: String::new(),
+                    partition: 0,
+                },
+                true,
+            );
+        }
+        data
+    }
+
+    fn labels(chunks: &[EntryChunk]) -> Vec<(String, usize)> {
+        chunks
+            .iter()
+            .map(|chunk| (chunk.label.to_owned(), chunk.entries.len()))
+            .collect()
+    }
+
+    #[test]
+    fn chunks() {
+        let data = data(&["名詞", "動詞一段", "名詞", "地名", "名詞"]);
+        assert_eq!(
+            labels(&data.split_user_entries(2, "order")),
+            [
+                ("01".to_owned(), 2),
+                ("02".to_owned(), 2),
+                ("03".to_owned(), 1)
+            ]
+        );
+        assert_eq!(
+            labels(&data.split_user_entries(2, "pos")),
+            [
+                ("名詞-01".to_owned(), 2),
+                ("名詞-02".to_owned(), 1),
+                ("地名-01".to_owned(), 1),
+                ("動詞一段-01".to_owned(), 1),
+            ]
+        );
+        assert_eq!(labels(&data.split_user_entries(0, "order")).len(), 5);
+    }
+
+    #[test]
+    fn split_file_names() {
+        let path = Path::new("out/user_dict.txt");
+        assert!(is_split_file(path, "user_dict-01.txt"));
+        assert!(is_split_file(path, "user_dict-105.txt"));
+        assert!(is_split_file(path, "user_dict-名詞-01.txt"));
+        assert!(!is_split_file(path, "user_dict.txt"));
+        assert!(!is_split_file(path, "user_dict-1.txt"));
+        assert!(!is_split_file(path, "user_dict-old.txt"));
+        assert!(!is_split_file(path, "user_dict-mine-01.txt"));
+        assert!(!is_split_file(path, "user_dict-01.txt.bak"));
+        assert!(!is_split_file(Path::new("dict"), "dict-01.txt"));
+        assert!(is_split_file(Path::new("dict"), "dict-01"));
+    }
+}
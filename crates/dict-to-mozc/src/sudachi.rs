@@ -0,0 +1,369 @@
+//! SudachiDictのバイナリ辞書(system_core.dic, system_full.dicなど)の読み込み
+//!
+//! ヘッダー、文法(品詞の一覧と連接表)、語彙(トライ、単語IDの表、単語のパラメーター、単語情報)の順に並ぶ。
+//! 単語情報を、SudachiDictの配布CSV(small_lex.csvなど)と同じ並びのレコードにする。
+//! 分割情報など変換に使わない列は `*` にしておく。
+use super::*;
+
+const SYSTEM_DICT_VERSION_1: u64 = 0x7366_d3f1_8bd1_11e7;
+const SYSTEM_DICT_VERSION_2: u64 = 0xce9f_011a_9239_4434;
+const USER_DICT_VERSION_1: u64 = 0xa50f_3118_8bd2_11e7;
+const USER_DICT_VERSION_2: u64 = 0x9fde_b5a9_0168_d868;
+const USER_DICT_VERSION_3: u64 = 0xca98_1175_6ff6_4fb0;
+
+/// バージョン、作成日時、説明(256バイト)
+const HEADER_SIZE: usize = 8 + 8 + 256;
+/// 品詞の階層の数
+const POS_DEPTH: usize = 6;
+
+/// バイナリ辞書を先頭から読む。
+struct DicReader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> DicReader<'a> {
+    fn new(bytes: &'a [u8], offset: usize) -> Self {
+        Self { bytes, offset }
+    }
+
+    fn take(&mut self, length: usize) -> io::Result<&'a [u8]> {
+        let end = self
+            .offset
+            .checked_add(length)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("truncated Sudachi dictionary at offset {}", self.offset),
+                )
+            })?;
+        let bytes = &self.bytes[self.offset..end];
+        self.offset = end;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> io::Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> io::Result<u16> {
+        Ok(u16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn i16(&mut self) -> io::Result<i16> {
+        Ok(i16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> io::Result<u32> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn i32(&mut self) -> io::Result<i32> {
+        Ok(i32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> io::Result<u64> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    /// 文字列の長さ。128以上の場合は2バイトで表す。
+    fn string_length(&mut self) -> io::Result<usize> {
+        let high = self.u8()? as usize;
+        if high < 128 {
+            return Ok(high);
+        }
+        let low = self.u8()? as usize;
+        Ok(((high & 0x7F) << 8) | low)
+    }
+
+    /// 長さとUTF-16LEの文字列
+    fn utf16_string(&mut self) -> io::Result<String> {
+        let length = self.string_length()?;
+        let (units, _) = self.take(length * 2)?.as_chunks::<2>();
+        let units: Vec<u16> = units.iter().map(|unit| u16::from_le_bytes(*unit)).collect();
+        Ok(String::from_utf16_lossy(&units))
+    }
+
+    /// 長さと32ビット整数の配列(分割情報など)を読み飛ばす。
+    fn i32_array(&mut self) -> io::Result<Vec<i32>> {
+        let length = self.u8()? as usize;
+        (0..length).map(|_| self.i32()).collect()
+    }
+
+    fn skip_i32_array(&mut self) -> io::Result<()> {
+        let length = self.u8()? as usize;
+        self.take(length * 4)?;
+        Ok(())
+    }
+}
+
+/// 読み込んだ内容の先頭がSudachiDictのバイナリ辞書のバージョンかどうか
+pub(crate) fn is_sudachi_dic(bytes: &[u8]) -> bool {
+    bytes.first_chunk::<8>().is_some_and(|version| {
+        matches!(
+            u64::from_le_bytes(*version),
+            SYSTEM_DICT_VERSION_1
+                | SYSTEM_DICT_VERSION_2
+                | USER_DICT_VERSION_1
+                | USER_DICT_VERSION_2
+                | USER_DICT_VERSION_3
+        )
+    })
+}
+
+/// 単語情報を、SudachiDictの配布CSVと同じ並びのレコードにする。
+fn sudachi_dic_record(
+    reader: &mut DicReader,
+    pos_list: &[Vec<String>],
+    params: (i16, i16, i16),
+    has_synonym_group_ids: bool,
+) -> io::Result<StringRecord> {
+    let surface = reader.utf16_string()?;
+    let _head_word_length = reader.string_length()?;
+    let pos_id = reader.u16()? as usize;
+    let normalized_form = reader.utf16_string()?;
+    let dictionary_form_word_id = reader.i32()?;
+    let reading_form = reader.utf16_string()?;
+    reader.skip_i32_array()?; // A単位分割情報
+    reader.skip_i32_array()?; // B単位分割情報
+    reader.skip_i32_array()?; // 語構成
+    let synonym_group_ids = if has_synonym_group_ids {
+        reader.i32_array()?
+    } else {
+        Vec::new()
+    };
+
+    let pos = pos_list.get(pos_id).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("unknown Sudachi POS id: {}", pos_id),
+        )
+    })?;
+    // 正規化表記と読みが空の場合は、見出しと同じ
+    let normalized_form = if normalized_form.is_empty() {
+        surface.clone()
+    } else {
+        normalized_form
+    };
+    let reading_form = if reading_form.is_empty() {
+        surface.clone()
+    } else {
+        reading_form
+    };
+    let dictionary_form = if dictionary_form_word_id < 0 {
+        "*".to_owned()
+    } else {
+        dictionary_form_word_id.to_string()
+    };
+
+    let (left_id, right_id, cost) = params;
+    let mut fields = vec![
+        surface.clone(),
+        left_id.to_string(),
+        right_id.to_string(),
+        cost.to_string(),
+        surface,
+    ];
+    fields.extend(pos.iter().cloned());
+    fields.extend([reading_form, normalized_form, dictionary_form]);
+    // 分割タイプ、A単位分割情報、B単位分割情報、未使用
+    fields.extend(["*", "*", "*", "*"].map(String::from));
+    // 同義語グループ番号。synonyms.txtと同じ6桁にする。
+    fields.push(if synonym_group_ids.is_empty() {
+        "*".to_owned()
+    } else {
+        synonym_group_ids
+            .iter()
+            .map(|id| format!("{:06}", id))
+            .collect::<Vec<_>>()
+            .join("/")
+    });
+    Ok(StringRecord::from(fields))
+}
+
+/// 読み込んだバイナリ辞書の単語情報を、レコードにしてprocessへ渡す。
+/// 単語情報の位置で前後するので、展開した辞書全体を受け取る。
+pub(crate) fn read_sudachi_dic(
+    bytes: &[u8],
+    process: &mut dyn FnMut(&StringRecord),
+) -> io::Result<()> {
+    let mut reader = DicReader::new(bytes, 0);
+
+    // ヘッダー
+    let version = reader.u64()?;
+    let has_synonym_group_ids = match version {
+        SYSTEM_DICT_VERSION_1 => false,
+        SYSTEM_DICT_VERSION_2 => true,
+        // ユーザー辞書の品詞IDは、システム辞書の品詞の一覧の続きになっている。
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Sudachi user dictionaries are not supported",
+            ));
+        }
+    };
+    reader.offset = HEADER_SIZE;
+
+    // 文法: 品詞の一覧と連接表
+    let pos_size = reader.u16()? as usize;
+    // 件数はファイルの値なので、壊れたファイルで大きな領域を確保しないように、先には確保しない。
+    let mut pos_list = Vec::new();
+    for _ in 0..pos_size {
+        let pos = (0..POS_DEPTH)
+            .map(|_| reader.utf16_string())
+            .collect::<io::Result<Vec<_>>>()?;
+        pos_list.push(pos);
+    }
+    let left_id_size = reader.i16()?.max(0) as usize;
+    let right_id_size = reader.i16()?.max(0) as usize;
+    reader.take(left_id_size * right_id_size * 2)?;
+
+    // 語彙: トライと単語IDの表は使わない。
+    let trie_size = reader.u32()? as usize;
+    reader.take(trie_size * 4)?;
+    let word_id_table_size = reader.u32()? as usize;
+    reader.take(word_id_table_size)?;
+
+    // 単語ごとの左連接ID、右連接ID、コスト
+    let word_size = reader.u32()? as usize;
+    let mut params = Vec::new();
+    for _ in 0..word_size {
+        params.push((reader.i16()?, reader.i16()?, reader.i16()?));
+    }
+
+    // 単語情報の位置(ファイルの先頭から)
+    let mut offsets = Vec::new();
+    for _ in 0..word_size {
+        offsets.push(reader.u32()? as usize);
+    }
+
+    for (offset, params) in offsets.into_iter().zip(params) {
+        let mut word_reader = DicReader::new(bytes, offset);
+        let record =
+            sudachi_dic_record(&mut word_reader, &pos_list, params, has_synonym_group_ids)?;
+        process(&record);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn put_string(buf: &mut Vec<u8>, s: &str) {
+        let units: Vec<u16> = s.encode_utf16().collect();
+        buf.push(units.len() as u8);
+        for unit in units {
+            buf.extend(unit.to_le_bytes());
+        }
+    }
+
+    /// 品詞1つ、単語1つのシステム辞書(バージョン2)。単語情報の位置を返す。
+    fn system_dic() -> (Vec<u8>, usize) {
+        let mut buf = SYSTEM_DICT_VERSION_2.to_le_bytes().to_vec();
+        buf.resize(HEADER_SIZE, 0);
+        // 文法
+        buf.extend(1u16.to_le_bytes());
+        for pos in ["名詞", "固有名詞", "地名", "一般", "*", "*"] {
+            put_string(&mut buf, pos);
+        }
+        buf.extend(1i16.to_le_bytes());
+        buf.extend(1i16.to_le_bytes());
+        buf.extend([0, 0]);
+        // トライ(2要素)と単語IDの表(3バイト)
+        buf.extend(2u32.to_le_bytes());
+        buf.extend([0xFF; 8]);
+        buf.extend(3u32.to_le_bytes());
+        buf.extend([0xFF; 3]);
+        // 単語のパラメーターと単語情報の位置
+        buf.extend(1u32.to_le_bytes());
+        for param in [5i16, 6, 3000] {
+            buf.extend(param.to_le_bytes());
+        }
+        let offset_position = buf.len();
+        buf.extend(0u32.to_le_bytes());
+        // 単語情報
+        let offset = buf.len();
+        buf[offset_position..offset_position + 4].copy_from_slice(&(offset as u32).to_le_bytes());
+        put_string(&mut buf, "東京");
+        buf.push(6);
+        buf.extend(0u16.to_le_bytes());
+        put_string(&mut buf, "");
+        buf.extend((-1i32).to_le_bytes());
+        put_string(&mut buf, "トウキョウ");
+        buf.push(0);
+        buf.push(1);
+        buf.extend(7i32.to_le_bytes());
+        buf.push(0);
+        buf.push(2);
+        buf.extend(2i32.to_le_bytes());
+        buf.extend(15i32.to_le_bytes());
+        (buf, offset)
+    }
+
+    fn read(bytes: &[u8]) -> io::Result<Vec<Vec<String>>> {
+        let mut records = Vec::new();
+        read_sudachi_dic(bytes, &mut |record| {
+            records.push(record.iter().map(str::to_string).collect());
+        })?;
+        Ok(records)
+    }
+
+    #[test]
+    fn word_info() {
+        let (bytes, _) = system_dic();
+        assert!(is_sudachi_dic(&bytes));
+        assert!(!is_sudachi_dic("東京,5,6,3000".as_bytes()));
+        assert_eq!(
+            read(&bytes).unwrap(),
+            [[
+                "東京",
+                "5",
+                "6",
+                "3000",
+                "東京",
+                "名詞",
+                "固有名詞",
+                "地名",
+                "一般",
+                "*",
+                "*",
+                "トウキョウ",
+                "東京",
+                "*",
+                "*",
+                "*",
+                "*",
+                "*",
+                "000002/000015"
+            ]]
+        );
+    }
+
+    #[test]
+    fn long_string_length() {
+        let mut reader = DicReader::new(&[0x81, 0x02], 0);
+        assert_eq!(reader.string_length().unwrap(), 0x102);
+    }
+
+    #[test]
+    fn out_of_range_offset() {
+        let (mut bytes, offset) = system_dic();
+        let position = offset - 4;
+        bytes[position..offset].copy_from_slice(&u32::MAX.to_le_bytes());
+        let error = read(&bytes).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+
+        // 単語数が実際より多い場合も、途中で止める。
+        let (mut bytes, offset) = system_dic();
+        let position = offset - 4 - 6 - 4;
+        bytes[position..position + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert_eq!(read(&bytes).unwrap_err().kind(), io::ErrorKind::InvalidData);
+
+        // ユーザー辞書は読まない。
+        let (mut bytes, _) = system_dic();
+        bytes[..8].copy_from_slice(&USER_DICT_VERSION_3.to_le_bytes());
+        assert_eq!(read(&bytes).unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+}
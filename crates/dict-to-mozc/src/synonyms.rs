@@ -0,0 +1,196 @@
+//! SudachiDictの同義語辞書(synonyms.txt)の読み込み
+//!
+//! 1行に1語で、空行でグループを区切る。
+//! 列は `グループ番号,体言/用言,展開制御,語彙素番号,語形種別,略語,表記揺れ,分野,見出し,...` の並び。
+//! 同じグループの同じ語彙素のうち、代表語の表記揺れ(略語、対訳、誤表記を除く)を、互いの別表記とする。
+//! 同義語辞書には読みがないため、代表表記どうしは、読みが確かめられる場合だけ別表記にする。
+//! 同じ見出しでも読みが違う語(開く: あく、ひらく)は別のグループになるので、
+//! 見出しとグループ番号で引き、辞書のエントリーの同義語グループ番号のグループだけを使う。
+use super::*;
+
+use encoding_rs::UTF_8;
+
+/// グループ番号と見出しから、別表記の一覧
+pub(crate) type Synonyms = IndexMap<(u32, String), Vec<Variant>, RandomState>;
+
+/// 別表記
+#[derive(Debug, PartialEq)]
+pub(crate) struct Variant {
+    pub(crate) surface: String,
+    /// 片方が表記揺れの行で、同じ読みになる。
+    pub(crate) is_spelling: bool,
+}
+
+/// 展開制御: 常に展開する。
+const EXPANSION_ALWAYS: &str = "0";
+/// 展開制御: 展開に使わない。
+const EXPANSION_NEVER: &str = "2";
+/// 語形種別: 代表語
+const FORM_TYPE_TYPICAL: &str = "0";
+/// 略語: 代表語形
+const ABBREVIATION_NONE: &str = "0";
+/// 表記揺れ: 代表表記
+const VARIANT_TYPE_TYPICAL: &str = "0";
+/// 表記揺れ: 表記揺れ
+const VARIANT_TYPE_SPELLING: &str = "2";
+
+/// 同義語辞書の1行
+struct SynonymLine {
+    group: u32,
+    lexeme: String,
+    /// 自分の見出しから、ほかの表記を出力する。
+    is_trigger: bool,
+    /// 別表記として出力する。
+    is_variant: bool,
+    /// 表記揺れの行
+    is_spelling: bool,
+    surface: String,
+}
+
+fn synonym_line(line: &str) -> Option<SynonymLine> {
+    let fields: Vec<&str> = line.split(',').collect();
+    if fields.len() < 9 || fields[8].is_empty() {
+        return None;
+    }
+    let group = fields[0].parse().ok()?;
+    let is_spelling = fields[4] == FORM_TYPE_TYPICAL
+        && fields[5] == ABBREVIATION_NONE
+        && [VARIANT_TYPE_TYPICAL, VARIANT_TYPE_SPELLING].contains(&fields[6]);
+    Some(SynonymLine {
+        group,
+        lexeme: fields[3].to_owned(),
+        is_trigger: is_spelling && fields[2] == EXPANSION_ALWAYS,
+        is_variant: is_spelling && fields[2] != EXPANSION_NEVER,
+        is_spelling: fields[6] == VARIANT_TYPE_SPELLING,
+        surface: fields[8].to_owned(),
+    })
+}
+
+/// 1つの語彙素の表記を、互いの別表記として追加する。
+fn add_lexeme(synonyms: &mut Synonyms, lexeme: &[SynonymLine]) {
+    for trigger in lexeme.iter().filter(|line| line.is_trigger) {
+        for variant in lexeme.iter().filter(|line| line.is_variant) {
+            if variant.surface == trigger.surface {
+                continue;
+            }
+            let is_spelling = trigger.is_spelling || variant.is_spelling;
+            let variants = synonyms
+                .entry((trigger.group, trigger.surface.to_owned()))
+                .or_default();
+            match variants.iter_mut().find(|v| v.surface == variant.surface) {
+                Some(v) => v.is_spelling |= is_spelling,
+                None => variants.push(Variant {
+                    surface: variant.surface.to_owned(),
+                    is_spelling,
+                }),
+            }
+        }
+    }
+}
+
+/// SudachiDictの同義語グループ番号の列(`000001/000002`)。値がない場合は `*`。
+pub(crate) fn synonym_groups(field: &str) -> Vec<u32> {
+    field
+        .trim_matches(['[', ']'])
+        .split(['/', ','])
+        .filter_map(|group| group.trim().parse().ok())
+        .collect()
+}
+
+pub(crate) fn read_synonyms(path: &Path, args: &Config) -> io::Result<Synonyms> {
+    let mut text = String::new();
+    decode_reader(
+        open_file(path, None, "*.txt", args.debug)?,
+        None,
+        Some(UTF_8),
+    )?
+    .read_to_string(&mut text)?;
+    let synonyms = parse_synonyms(&text);
+    if args.debug > 1 {
+        eprintln!("Synonyms: {} surfaces from {:?}", synonyms.len(), path);
+    }
+    Ok(synonyms)
+}
+
+fn parse_synonyms(text: &str) -> Synonyms {
+    let mut synonyms = Synonyms::default();
+    // 同じグループ、同じ語彙素番号の行
+    let mut lexeme: Vec<SynonymLine> = Vec::new();
+    for line in text.lines() {
+        let line = synonym_line(line.trim());
+        let is_same_lexeme = match (&line, lexeme.last()) {
+            (Some(line), Some(last)) => line.group == last.group && line.lexeme == last.lexeme,
+            _ => false,
+        };
+        if !is_same_lexeme {
+            add_lexeme(&mut synonyms, &lexeme);
+            lexeme.clear();
+        }
+        lexeme.extend(line);
+    }
+    add_lexeme(&mut synonyms, &lexeme);
+    synonyms
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn variants<'a>(synonyms: &'a Synonyms, group: u32, surface: &str) -> Vec<(&'a str, bool)> {
+        synonyms
+            .get(&(group, surface.to_owned()))
+            .map(|v| {
+                v.iter()
+                    .map(|v| (v.surface.as_str(), v.is_spelling))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn spelling_variants() {
+        let synonyms = parse_synonyms(
+            "000001,1,0,1,0,0,0,(),附属,,\n\
+             000001,1,0,1,0,0,2,(),付属,,\n\
+             000001,1,0,1,0,0,3,(),付族,,\n\
+             000001,1,0,2,0,0,0,(),アタッチメント,,\n\
+             \n\
+             000002,1,0,1,0,0,0,(),空港,,\n\
+             000002,1,0,1,0,1,0,(),空,,\n\
+             000002,1,0,1,0,0,0,(),くうこう,,\n\
+             000002,1,2,1,0,0,2,(),空こう,,\n\
+             000002,1,1,1,0,0,2,(),くう港,,\n",
+        );
+        assert_eq!(variants(&synonyms, 1, "附属"), [("付属", true)]);
+        assert_eq!(variants(&synonyms, 1, "付属"), [("附属", true)]);
+        assert!(variants(&synonyms, 1, "付族").is_empty());
+        assert!(variants(&synonyms, 2, "附属").is_empty());
+        assert_eq!(
+            variants(&synonyms, 2, "空港"),
+            [("くうこう", false), ("くう港", true)]
+        );
+        assert_eq!(
+            variants(&synonyms, 2, "くうこう"),
+            [("空港", false), ("くう港", true)]
+        );
+        assert!(variants(&synonyms, 2, "くう港").is_empty());
+        assert!(variants(&synonyms, 2, "空こう").is_empty());
+    }
+
+    #[test]
+    fn homograph_groups() {
+        // 開く(あく)と開く(ひらく)は、別のグループの語
+        let synonyms = parse_synonyms(
+            "000003,2,0,1,0,0,0,(),開く,,\n\
+             000003,2,0,1,0,0,2,(),明く,,\n\
+             \n\
+             000004,2,0,1,0,0,0,(),開く,,\n\
+             000004,2,0,1,0,0,2,(),拓く,,\n",
+        );
+        assert_eq!(variants(&synonyms, 3, "開く"), [("明く", true)]);
+        assert_eq!(variants(&synonyms, 4, "開く"), [("拓く", true)]);
+        assert_eq!(synonym_groups("000003/000010"), [3, 10]);
+        assert_eq!(synonym_groups("[4]"), [4]);
+        assert!(synonym_groups("*").is_empty());
+    }
+}
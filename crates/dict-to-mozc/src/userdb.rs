@@ -0,0 +1,435 @@
+//! Mozcのユーザー辞書ファイル(user_dictionary.db)の読み込みと書き込み
+//!
+//! user_dictionary.dbは、UserDictionaryStorageのprotobufをそのまま保存したファイル。
+//! ```text
+//! UserDictionaryStorage { repeated UserDictionary dictionaries = 2; }
+//! UserDictionary { uint64 id = 1; bool enabled = 2; string name = 3; repeated Entry entries = 4; }
+//! Entry { string key = 1; string value = 2; string comment = 4; PosType pos = 5; bool removed = 10; }
+//! ```
+//! 品詞(PosType)は、ユーザー辞書の品詞名の並び順(1から)の番号になる。
+//! 読み込んだエントリーは、Mozcユーザー辞書型式と同じ `よみ\t表記\t品詞\tコメント` のレコードにする。
+use super::*;
+
+/// ユーザー辞書の品詞名。PosTypeの番号順(名詞 = 1)。
+pub(crate) const USER_POS: [&str; 44] = [
+    "名詞",
+    "短縮よみ",
+    "サジェストのみ",
+    "固有名詞",
+    "人名",
+    "姓",
+    "名",
+    "組織",
+    "地名",
+    "名詞サ変",
+    "名詞形動",
+    "数",
+    "アルファベット",
+    "記号",
+    "顔文字",
+    "副詞",
+    "連体詞",
+    "接続詞",
+    "感動詞",
+    "接頭語",
+    "助数詞",
+    "接尾一般",
+    "接尾人名",
+    "接尾地名",
+    "動詞ワ行五段",
+    "動詞カ行五段",
+    "動詞サ行五段",
+    "動詞タ行五段",
+    "動詞ナ行五段",
+    "動詞マ行五段",
+    "動詞ラ行五段",
+    "動詞ガ行五段",
+    "動詞バ行五段",
+    "動詞ハ行四段",
+    "動詞一段",
+    "動詞カ変",
+    "動詞サ変",
+    "動詞ザ変",
+    "動詞ラ変",
+    "形容詞",
+    "終助詞",
+    "句読点",
+    "独立語",
+    "抑制単語",
+];
+
+/// 長さで区切られた値(文字列、メッセージ)
+const WIRE_LENGTH_DELIMITED: u64 = 2;
+/// 整数、真偽値、列挙型
+const WIRE_VARINT: u64 = 0;
+/// 64ビット固定長
+const WIRE_FIXED64: u64 = 1;
+/// 32ビット固定長
+const WIRE_FIXED32: u64 = 5;
+
+/// ユーザー辞書の品詞名をPosTypeの番号にする。
+/// Mozcのユーザー辞書にない品詞(助動詞など)は名詞にする。
+pub(crate) fn user_pos_number(word_class: &str) -> u64 {
+    USER_POS
+        .iter()
+        .position(|pos| *pos == word_class)
+        .map_or(1, |index| index as u64 + 1)
+}
+
+fn put_varint(buf: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buf.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    buf.push(value as u8);
+}
+
+fn put_uint(buf: &mut Vec<u8>, field: u64, value: u64) {
+    put_varint(buf, (field << 3) | WIRE_VARINT);
+    put_varint(buf, value);
+}
+
+fn put_bytes(buf: &mut Vec<u8>, field: u64, bytes: &[u8]) {
+    put_varint(buf, (field << 3) | WIRE_LENGTH_DELIMITED);
+    put_varint(buf, bytes.len() as u64);
+    buf.extend_from_slice(bytes);
+}
+
+/// 辞書のID。Mozcは乱数で決めるが、同じ名前から同じファイルができるように名前から求める(FNV-1a)。
+fn dictionary_id(name: &str) -> u64 {
+    let id = name.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    });
+    // 0は未設定とみなされる。
+    id.max(1)
+}
+
+fn encode_entry(entry: &DictionaryEntry) -> Vec<u8> {
+    let mut buf = Vec::new();
+    put_bytes(&mut buf, 1, entry.key.pronunciation.as_bytes());
+    put_bytes(&mut buf, 2, entry.key.notation.as_bytes());
+    if !entry.comment.is_empty() {
+        put_bytes(&mut buf, 4, entry.comment.as_bytes());
+    }
+    put_uint(&mut buf, 5, user_pos_number(&entry.word_class));
+    buf
+}
+
+/// 名前の付いた1つの辞書
+fn encode_dictionary<'a>(
+    name: &str,
+    entries: impl Iterator<Item = &'a DictionaryEntry>,
+) -> Vec<u8> {
+    let mut buf = Vec::new();
+    put_uint(&mut buf, 1, dictionary_id(name));
+    put_uint(&mut buf, 2, 1);
+    put_bytes(&mut buf, 3, name.as_bytes());
+    for entry in entries {
+        put_bytes(&mut buf, 4, &encode_entry(entry));
+    }
+    buf
+}
+
+impl DictionaryData {
+    /// Mozcのユーザー辞書ファイル(user_dictionary.db)型式で出力する。
+    /// 品詞名はユーザー辞書型式で求めたものを使うので、ユーザー辞書のエントリーを出力する。
+    /// split_entriesの指定がある場合は、`辞書名-01`、`辞書名-02` ... の辞書に分ける。
+    pub fn output_user_db(&self, writer: &mut dyn Write, args: &Config) -> io::Result<()> {
+        let Some(size) = args.split_entries else {
+            return write_user_db(writer, &args.user_db_name, self.user_entries.values());
+        };
+        let mut storage = Vec::new();
+        for chunk in self.split_user_entries(size, &args.split_order) {
+            let name = format!("{}-{}", args.user_db_name, chunk.label);
+            let dictionary = encode_dictionary(&name, chunk.entries.iter().copied());
+            put_bytes(&mut storage, 2, &dictionary);
+        }
+        writer.write_all(&storage)
+    }
+}
+
+/// 1つの辞書だけのuser_dictionary.dbを書き込む。
+pub(crate) fn write_user_db<'a>(
+    writer: &mut dyn Write,
+    name: &str,
+    entries: impl Iterator<Item = &'a DictionaryEntry>,
+) -> io::Result<()> {
+    let mut storage = Vec::new();
+    put_bytes(&mut storage, 2, &encode_dictionary(name, entries));
+    writer.write_all(&storage)
+}
+
+/// protobufの値
+enum Field<'a> {
+    Varint(u64),
+    Bytes(&'a [u8]),
+}
+
+fn invalid_user_db(message: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("invalid user_dictionary.db: {}", message),
+    )
+}
+
+/// protobufのメッセージを先頭から読む。
+struct FieldReader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> FieldReader<'a> {
+    fn varint(&mut self) -> io::Result<u64> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let (&byte, rest) = self
+                .bytes
+                .split_first()
+                .ok_or_else(|| invalid_user_db("truncated varint"))?;
+            self.bytes = rest;
+            value |= u64::from(byte & 0x7F) << shift;
+            if byte < 0x80 {
+                return Ok(value);
+            }
+        }
+        Err(invalid_user_db("varint too long"))
+    }
+
+    fn take(&mut self, length: usize) -> io::Result<&'a [u8]> {
+        if length > self.bytes.len() {
+            return Err(invalid_user_db("truncated field"));
+        }
+        let (bytes, rest) = self.bytes.split_at(length);
+        self.bytes = rest;
+        Ok(bytes)
+    }
+
+    /// 次のフィールドの番号と値。固定長の値は使わないので読み飛ばす。
+    fn next_field(&mut self) -> io::Result<Option<(u64, Option<Field<'a>>)>> {
+        if self.bytes.is_empty() {
+            return Ok(None);
+        }
+        let tag = self.varint()?;
+        let value = match tag & 0x07 {
+            WIRE_VARINT => Some(Field::Varint(self.varint()?)),
+            WIRE_LENGTH_DELIMITED => {
+                let length = self.varint()? as usize;
+                Some(Field::Bytes(self.take(length)?))
+            }
+            WIRE_FIXED64 => {
+                self.take(8)?;
+                None
+            }
+            WIRE_FIXED32 => {
+                self.take(4)?;
+                None
+            }
+            _ => return Err(invalid_user_db("unknown wire type")),
+        };
+        Ok(Some((tag >> 3, value)))
+    }
+}
+
+fn utf8_field(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(bytes).into_owned()
+}
+
+/// Entryを `よみ,表記,品詞,コメント` のレコードにする。削除されたエントリーはNoneにする。
+fn decode_entry(bytes: &[u8]) -> io::Result<Option<StringRecord>> {
+    let mut reader = FieldReader { bytes };
+    let mut key = String::new();
+    let mut value = String::new();
+    let mut comment = String::new();
+    let mut pos = 1;
+    let mut removed = false;
+    while let Some((field, data)) = reader.next_field()? {
+        match (field, data) {
+            (1, Some(Field::Bytes(bytes))) => key = utf8_field(bytes),
+            (2, Some(Field::Bytes(bytes))) => value = utf8_field(bytes),
+            (4, Some(Field::Bytes(bytes))) => comment = utf8_field(bytes),
+            (5, Some(Field::Varint(number))) => pos = number,
+            (10, Some(Field::Varint(value))) => removed = value != 0,
+            _ => {}
+        }
+    }
+    if removed {
+        return Ok(None);
+    }
+    // 品詞の指定がないものは名詞にする。
+    let word_class = USER_POS
+        .get((pos as usize).wrapping_sub(1))
+        .unwrap_or(&USER_POS[0]);
+    Ok(Some(StringRecord::from(vec![
+        key.as_str(),
+        value.as_str(),
+        word_class,
+        comment.as_str(),
+    ])))
+}
+
+/// UserDictionaryのエントリーを読む。無効にされた辞書は読み込まない。
+fn decode_dictionary(
+    bytes: &[u8],
+    args: &Config,
+    process: &mut dyn FnMut(&StringRecord),
+) -> io::Result<()> {
+    let mut reader = FieldReader { bytes };
+    let mut name = String::new();
+    let mut enabled = true;
+    let mut entries = Vec::new();
+    while let Some((field, data)) = reader.next_field()? {
+        match (field, data) {
+            (2, Some(Field::Varint(value))) => enabled = value != 0,
+            (3, Some(Field::Bytes(bytes))) => name = utf8_field(bytes),
+            (4, Some(Field::Bytes(bytes))) => entries.push(bytes),
+            _ => {}
+        }
+    }
+    if args.debug > 1 {
+        eprintln!(
+            "Dictionary: {} ({} entries{})",
+            name,
+            entries.len(),
+            if enabled { "" } else { ", disabled" }
+        );
+    }
+    if !enabled {
+        return Ok(());
+    }
+    for entry in entries {
+        if let Some(record) = decode_entry(entry)? {
+            process(&record);
+        }
+    }
+    Ok(())
+}
+
+/// ファイルがUserDictionaryStorageかどうか
+/// 最初の辞書(dictionaries = 2)までのフィールドが読め、その辞書もすべてフィールドとして読めるかで見分ける。
+pub(crate) fn is_user_db(bytes: &[u8]) -> bool {
+    let mut reader = FieldReader { bytes };
+    while let Ok(Some((field, data))) = reader.next_field() {
+        match (field, data) {
+            (2, Some(Field::Bytes(dictionary))) => {
+                let mut reader = FieldReader { bytes: dictionary };
+                return std::iter::from_fn(|| reader.next_field().transpose())
+                    .all(|field| field.is_ok());
+            }
+            (2, _) => return false,
+            _ => {}
+        }
+    }
+    false
+}
+
+/// UserDictionaryStorageのすべての辞書のエントリーを読む。
+pub(crate) fn read_user_db(
+    bytes: &[u8],
+    args: &Config,
+    process: &mut dyn FnMut(&StringRecord),
+) -> io::Result<()> {
+    let mut reader = FieldReader { bytes };
+    while let Some((field, data)) = reader.next_field()? {
+        if let (2, Some(Field::Bytes(dictionary))) = (field, data) {
+            decode_dictionary(dictionary, args, process)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(
+        pronunciation: &str,
+        notation: &str,
+        word_class: &str,
+        comment: &str,
+    ) -> DictionaryEntry {
+        DictionaryEntry {
+            key: DictionaryKey {
+                pronunciation: pronunciation.to_owned(),
+                notation: notation.to_owned(),
+                word_class_id: 0,
+            },
+            cost: 0,
+            word_class: word_class.to_owned(),
+            comment: comment.to_owned(),
+            partition: 0,
+        }
+    }
+
+    fn read_records(bytes: &[u8]) -> Vec<Vec<String>> {
+        let mut records = Vec::new();
+        read_user_db(bytes, &Config::default(), &mut |record| {
+            records.push(record.iter().map(str::to_owned).collect())
+        })
+        .unwrap();
+        records
+    }
+
+    #[test]
+    fn round_trip() {
+        // 200バイトの読みで、長さのvarintが2バイトになる。
+        let long = "あ".repeat(200 / 3 + 1);
+        let entries = [
+            entry("とうきょう", "東京", "地名", "首都、日本"),
+            entry(&long, "亜", "動詞ワ行五段", ""),
+            entry("ほげ", "hoge", "助動詞", ""),
+        ];
+        let mut bytes = Vec::new();
+        write_user_db(&mut bytes, "辞書", entries.iter()).unwrap();
+        assert!(is_user_db(&bytes));
+        assert_eq!(
+            read_records(&bytes),
+            [
+                ["とうきょう", "東京", "地名", "首都、日本"],
+                [long.as_str(), "亜", "動詞ワ行五段", ""],
+                // ユーザー辞書にない品詞は名詞になる。
+                ["ほげ", "hoge", "名詞", ""],
+            ]
+        );
+        assert_eq!(user_pos_number("抑制単語"), 44);
+    }
+
+    #[test]
+    fn removed_and_disabled() {
+        let mut removed = encode_entry(&entry("けす", "消す", "動詞サ行五段", ""));
+        put_uint(&mut removed, 10, 1);
+        let mut enabled = Vec::new();
+        put_uint(&mut enabled, 2, 1);
+        put_bytes(
+            &mut enabled,
+            4,
+            &encode_entry(&entry("のこす", "残す", "動詞サ行五段", "")),
+        );
+        put_bytes(&mut enabled, 4, &removed);
+        let mut disabled = Vec::new();
+        put_uint(&mut disabled, 2, 0);
+        put_bytes(
+            &mut disabled,
+            4,
+            &encode_entry(&entry("むこう", "無効", "名詞", "")),
+        );
+        let mut storage = Vec::new();
+        put_uint(&mut storage, 1, 1);
+        put_bytes(&mut storage, 2, &enabled);
+        put_bytes(&mut storage, 2, &disabled);
+        assert!(is_user_db(&storage));
+        assert_eq!(
+            read_records(&storage),
+            [["のこす", "残す", "動詞サ行五段", ""]]
+        );
+    }
+
+    #[test]
+    fn text_is_not_user_db() {
+        assert!(!is_user_db(b""));
+        // 辞書の長さや中身がフィールドとして合わない
+        assert!(!is_user_db(b"\x08\x01\x12\x40short"));
+        assert!(!is_user_db("\x12\x05あいう".as_bytes()));
+        assert!(!is_user_db("あいう\t亜\t名詞\n".as_bytes()));
+        // 辞書のフィールドがない
+        assert!(!is_user_db(b"\x08\x01"));
+    }
+}
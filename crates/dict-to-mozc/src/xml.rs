@@ -0,0 +1,35 @@
+//! XML型式の辞書の読み込みで使う共通処理
+use std::io;
+
+use quick_xml::escape::resolve_predefined_entity;
+use quick_xml::events::{BytesRef, BytesText};
+
+/// XMLの読み込みエラーをio::Errorにする。
+pub(crate) fn xml_error<E: Into<quick_xml::Error>>(e: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e.into())
+}
+
+/// テキストを追加する。
+pub(crate) fn push_text(text: &mut String, event: &BytesText) -> io::Result<()> {
+    text.push_str(&event.xml_content().map_err(xml_error)?);
+    Ok(())
+}
+
+/// 文字参照と定義済みの実体参照を文字にして追加する。
+/// それ以外の実体参照は、`&名前;` のまま残す。
+pub(crate) fn push_reference(text: &mut String, reference: &BytesRef) -> io::Result<()> {
+    if let Some(ch) = reference.resolve_char_ref().map_err(xml_error)? {
+        text.push(ch);
+        return Ok(());
+    }
+    let name = reference.decode().map_err(xml_error)?;
+    match resolve_predefined_entity(&name) {
+        Some(s) => text.push_str(s),
+        None => {
+            text.push('&');
+            text.push_str(&name);
+            text.push(';');
+        }
+    }
+    Ok(())
+}
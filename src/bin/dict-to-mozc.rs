@@ -74,22 +74,86 @@ use std::process::ExitCode;
 /// (Mozc辞書型式への変換プログラム)
 #[derive(Debug)]
 struct Args {
-    /// path to the dictionary CSV file(TSV with -d $'\t' or -d TAB)
+    /// path to the dictionary CSV file(TSV with -d $'\t' or -d TAB), repeatable; - for stdin, directories are searched recursively with -g
     #[argh(option, short = 'f')]
-    csv_file: Option<PathBuf>,
+    csv_file: Vec<PathBuf>,
+
+    /// file name pattern for directories given by -f (初期値 *.csv、形式により異なる)
+    #[argh(option, short = 'g')]
+    glob: Option<String>,
+
+    /// file to read from a zip archive given by -f (初期値 -gのパターンに合うすべてのファイル)
+    #[argh(option)]
+    zip_member: Option<String>,
 
     /// path to the Mozc id.def file(Default is ./id.def)
     #[argh(option, short = 'i')]
     id_def: Option<PathBuf>,
 
+    /// output file, written atomically (指定しない場合、標準出力)
+    #[argh(option, short = 'o')]
+    output: Option<PathBuf>,
+
     /// generate Mozc User Dictionary formats(指定しない場合、Mozcシステム辞書型式で出力)
     #[argh(switch, short = 'U')]
     user_dict: bool,
 
+    /// generate macOS user dictionary plist formats (macOSのユーザー辞書型式で出力)
+    #[argh(switch, short = 'X')]
+    plist_output: bool,
+
+    /// generate Mozc user_dictionary.db (protobuf) with -o (ユーザー辞書ファイルで出力)
+    #[argh(switch)]
+    user_db: bool,
+
+    /// dictionary name in user_dictionary.db (初期値 dict-to-mozc)
+    #[argh(option)]
+    user_db_name: Option<String>,
+
+    /// split the user dictionary into files (-U with -o) or dictionaries (--user-db) of at most N entries
+    #[argh(option)]
+    split: Option<usize>,
+
+    /// order of --split: order, pos (初期値 order、読み込んだ順)
+    #[argh(option)]
+    split_order: Option<String>,
+
+    /// write a file per category (人名, 地名, 組織, 記号, 一般) with -o
+    #[argh(switch)]
+    partition: bool,
+
+    /// rules for --partition: lines of name<TAB>word class (ユーザー辞書の品詞名、またはid.defの品詞の先頭)
+    #[argh(option)]
+    partition_rules: Option<PathBuf>,
+
+    /// write the system dictionary sorted into N files dictionary00.txt ... in the -o directory, like Mozc OSS (10)
+    #[argh(option)]
+    shards: Option<usize>,
+
+    /// sort key for --shards: reading, notation, id, cost, order (初期値 reading)
+    #[argh(option)]
+    shard_sort: Option<String>,
+
+    /// file name prefix for --shards, to write next to Mozc OSS files (初期値 dictionary)
+    #[argh(option)]
+    shard_prefix: Option<String>,
+
+    /// remove numbered --shards files left over from an earlier run with more files
+    #[argh(switch)]
+    shard_clean: bool,
+
     /// target SudachiDict
     #[argh(switch, short = 's')]
     sudachi: bool,
 
+    /// synonyms.txt of SudachiDict, adding the spelling variants (表記揺れ) with -s
+    #[argh(option)]
+    synonyms: Option<PathBuf>,
+
+    /// cost added to the spelling variants from --synonyms (初期値 200)
+    #[argh(option)]
+    synonym_cost_penalty: Option<i32>,
+
     /// target NEologd dictionary
     #[argh(switch, short = 'n')]
     neologd: bool,
@@ -102,6 +166,90 @@ struct Args {
     #[argh(switch, short = 'M')]
     mozcuserdict: bool,
 
+    /// target Mozc System Dictionary (dictionary00.txt ... dictionary09.txt)
+    #[argh(switch, short = 'O')]
+    mozcsystemdict: bool,
+
+    /// path to the id.def file the Mozc System Dictionary was built with(Default is the same as -i)
+    #[argh(option)]
+    source_id_def: Option<PathBuf>,
+
+    /// rewrite the ids of a Mozc System Dictionary or UT dictionary from --source-id-def to -i id.def (品詞IDの移行)
+    #[argh(switch)]
+    migrate: bool,
+
+    /// target SKK dictionary (SKK-JISYO, EUC-JP or UTF-8, detected automatically)
+    #[argh(switch, short = 'k')]
+    skk: bool,
+
+    /// target Canna/Anthy dictionary (cannadic, EUC-JP or UTF-8, detected automatically)
+    #[argh(switch, short = 'c')]
+    canna: bool,
+
+    /// target MeCab dictionary source (mecab-ipadic, unidic-mecab CSV file or directory)
+    #[argh(switch, short = 'm')]
+    mecab: bool,
+
+    /// reading column for MeCab dictionary: reading, pronunciation, lemma-reading (初期値 reading)
+    #[argh(option)]
+    mecab_reading: Option<String>,
+
+    /// target Microsoft IME / Google Japanese Input exported user dictionary
+    #[argh(switch, short = 'I')]
+    ime: bool,
+
+    /// target ATOK exported word list
+    #[argh(switch, short = 'A')]
+    atok: bool,
+
+    /// target macOS user dictionary / text replacements (XML plist)
+    #[argh(switch, short = 'x')]
+    plist: bool,
+
+    /// target JMdict XML (JMdict_e.xml)
+    #[argh(switch, short = 'J')]
+    jmdict: bool,
+
+    /// target JMnedict XML (JMnedict.xml)
+    #[argh(switch, short = 'j')]
+    jmnedict: bool,
+
+    /// target Japan Post postal code data (KEN_ALL.CSV, needs -p)
+    #[argh(switch, short = 'z')]
+    ken_all: bool,
+
+    /// target Aozora Bunko texts (file or directory), reading the ruby
+    #[argh(switch, short = 'a')]
+    aozora: bool,
+
+    /// word class for Aozora Bunko ruby (初期値 名詞,普通名詞,一般,*,*,*)
+    #[argh(option)]
+    aozora_word_class: Option<String>,
+
+    /// target JSON / JSONL word list (JSON配列、または1行1オブジェクト)
+    #[argh(switch)]
+    json: bool,
+
+    /// JSON key or JSON Pointer for the reading (初期値 reading)
+    #[argh(option)]
+    json_pronunciation: Option<String>,
+
+    /// JSON key or JSON Pointer for the notation (初期値 surface)
+    #[argh(option)]
+    json_notation: Option<String>,
+
+    /// JSON key or JSON Pointer for the word class (初期値 pos)
+    #[argh(option)]
+    json_word_class: Option<String>,
+
+    /// JSON key or JSON Pointer for the cost
+    #[argh(option)]
+    json_cost: Option<String>,
+
+    /// JSON key or JSON Pointer for the frequency, converted to the cost (初期値 freq)
+    #[argh(option)]
+    json_frequency: Option<String>,
+
     /// include place names (地名を含める)
     #[argh(switch, short = 'p')]
     places: bool,
@@ -110,25 +258,33 @@ struct Args {
     #[argh(switch, short = 'S')]
     symbols: bool,
 
-    /// pronunciation 読みフィールドの位置（0から始まる）
+    /// input and id.def encoding: auto, utf-8, shift_jis, euc-jp, utf-16le, utf-16be (初期値 auto)
+    #[argh(option, short = 'e')]
+    encoding: Option<String>,
+
+    /// the first row is a header of column names (1行目を列名とみなす)
+    #[argh(switch, short = 'H')]
+    header: bool,
+
+    /// pronunciation 読みフィールドの位置（0から始まる）、または列名(-H)
     #[argh(option, short = 'P')]
-    pronunciation_index: Option<usize>,
+    pronunciation_index: Option<String>,
 
-    /// notation 表記フィールドの位置（0から始まる）
+    /// notation 表記フィールドの位置（0から始まる）、または列名(-H)
     #[argh(option, short = 'N')]
-    notation_index: Option<usize>,
+    notation_index: Option<String>,
 
-    /// word class 品詞判定フィールドの位置（0から始まる）
+    /// word class 品詞判定フィールドの位置（0から始まる）、または列名(-H)、範囲(pos1..pos6)
     #[argh(option, short = 'W')]
-    word_class_index: Option<usize>,
+    word_class_index: Option<String>,
 
     /// word class 品詞判定フィールドのフィールド数
     #[argh(option, short = 'w')]
     word_class_numbers: Option<usize>,
 
-    /// cost コストフィールドの位置（0から始まる）
+    /// cost コストフィールドの位置（0から始まる）、または列名(-H)
     #[argh(option, short = 'C')]
-    cost_index: Option<usize>,
+    cost_index: Option<String>,
 
     /// delimiter デリミタ(初期値 ',' カンマ)
     #[argh(option, short = 'd')]
@@ -145,6 +301,18 @@ enum DictType {
     UTDict,
     NEologd,
     MozcUserDict,
+    Skk,
+    Canna,
+    Mecab,
+    Ime,
+    Atok,
+    Plist,
+    Jmdict,
+    Jmnedict,
+    KenAll,
+    MozcSystemDict,
+    Aozora,
+    Json,
 }
 
 impl Args {
@@ -158,40 +326,225 @@ impl Args {
             DictType::NEologd
         } else if self.mozcuserdict {
             DictType::MozcUserDict
+        } else if self.skk {
+            DictType::Skk
+        } else if self.canna {
+            DictType::Canna
+        } else if self.mecab {
+            DictType::Mecab
+        } else if self.ime {
+            DictType::Ime
+        } else if self.atok {
+            DictType::Atok
+        } else if self.plist {
+            DictType::Plist
+        } else if self.jmdict {
+            DictType::Jmdict
+        } else if self.jmnedict {
+            DictType::Jmnedict
+        } else if self.ken_all {
+            DictType::KenAll
+        } else if self.mozcsystemdict {
+            DictType::MozcSystemDict
+        } else if self.aozora {
+            DictType::Aozora
+        } else if self.json {
+            DictType::Json
         } else {
             DictType::Default
         };
 
-        Ok(Config {
-            csv_file: self.csv_file.unwrap_or_else(|| current_dir.join("all.csv")),
+        let mecab_reading = self.mecab_reading.unwrap_or_else(|| "reading".to_owned());
+        if !MECAB_READINGS.contains(&mecab_reading.as_str()) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("unknown MeCab reading column: {}", mecab_reading),
+            ));
+        }
+
+        let split_order = self.split_order.unwrap_or_else(|| "order".to_owned());
+        if !SPLIT_ORDERS.contains(&split_order.as_str()) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("unknown split order: {}", split_order),
+            ));
+        }
+        if let Some(split) = self.split {
+            // 分けるのはユーザー辞書のエントリーだけ
+            let message = if split == 0 {
+                Some("--split needs at least 1 entry")
+            } else if !self.user_dict && !self.user_db {
+                Some("--split needs -U or --user-db")
+            } else if !self.user_db && self.output.is_none() {
+                Some("--split needs -o to name the files")
+            } else {
+                None
+            };
+            if let Some(message) = message {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    message,
+                ));
+            }
+        }
+
+        let partitions = match &self.partition_rules {
+            Some(path) => read_partition_rules(path)?,
+            None if self.partition => default_partition_rules(),
+            None => Vec::new(),
+        };
+        if !partitions.is_empty() {
+            let message = if self.output.is_none() {
+                Some("--partition needs -o to name the files")
+            } else if self.split.is_some() {
+                Some("--partition can not be used with --split")
+            } else {
+                None
+            };
+            if let Some(message) = message {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    message,
+                ));
+            }
+        }
+
+        let shard_sort = self.shard_sort.unwrap_or_else(|| "reading".to_owned());
+        if !SHARD_SORTS.contains(&shard_sort.as_str()) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("unknown shard sort: {}", shard_sort),
+            ));
+        }
+        if let Some(shards) = self.shards {
+            // 分けるのはシステム辞書型式だけ
+            let message = if shards == 0 {
+                Some("--shards needs at least 1 file")
+            } else if self.output.is_none() {
+                Some("--shards needs -o for the output directory")
+            } else if self.user_dict || self.user_db || self.plist_output {
+                Some("--shards is only for the system dictionary format")
+            } else if self.split.is_some() || !partitions.is_empty() {
+                Some("--shards can not be used with --split or --partition")
+            } else {
+                None
+            };
+            if let Some(message) = message {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    message,
+                ));
+            }
+        }
+
+        if self.migrate && self.source_id_def.is_none() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "--migrate requires --source-id-def",
+            ));
+        }
+        if self.user_db && self.output.is_none() {
+            // バイナリを端末や、ほかのコマンドの入力に流さない。
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "--user-db needs -o for the binary file",
+            ));
+        }
+        if self.synonyms.is_some() && !self.sudachi {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "--synonyms requires -s",
+            ));
+        }
+
+        let encoding = encoding_for_label(self.encoding.as_deref().unwrap_or("auto"))?;
+
+        let columns = ColumnNames {
+            pronunciation: self.pronunciation_index,
+            notation: self.notation_index,
+            word_class: self.word_class_index,
+            word_class_numbers: self.word_class_numbers,
+            cost: self.cost_index,
+        };
+
+        let mut config = Config {
+            csv_files: if self.csv_file.is_empty() {
+                vec![current_dir.join("all.csv")]
+            } else {
+                self.csv_file
+            },
+            glob: self.glob.unwrap_or_else(|| dict_type.default_glob()),
             id_def: self.id_def.unwrap_or_else(|| current_dir.join("id.def")),
-            pronunciation_index: self
-                .pronunciation_index
-                .unwrap_or_else(|| dict_type.default_pronunciation_index()),
-            notation_index: self
-                .notation_index
-                .unwrap_or_else(|| dict_type.default_notation_index()),
-            word_class_index: self
-                .word_class_index
-                .unwrap_or_else(|| dict_type.default_word_class_index()),
-            word_class_numbers: self
-                .word_class_numbers
-                .unwrap_or_else(|| dict_type.default_word_class_numbers()),
-            cost_index: self
-                .cost_index
-                .unwrap_or_else(|| dict_type.default_cost_index()),
+            pronunciation_index: dict_type.default_pronunciation_index(),
+            notation_index: dict_type.default_notation_index(),
+            word_class_index: dict_type.default_word_class_index(),
+            word_class_numbers: dict_type.default_word_class_numbers(),
+            cost_index: dict_type.default_cost_index(),
             delimiter: self
                 .delimiter
                 .unwrap_or_else(|| dict_type.default_delimiter()),
+            has_header: self.header,
+            encoding,
+            zip_member: self.zip_member,
             sudachi: self.sudachi,
+            sudachi_synonyms: self.synonyms,
+            synonym_cost_penalty: self.synonym_cost_penalty.unwrap_or(200),
             utdict: self.utdict,
             neologd: self.neologd,
             mozcuserdict: self.mozcuserdict,
-            user_dict: self.user_dict,
+            skk: self.skk,
+            canna: self.canna,
+            mecab: self.mecab,
+            mecab_reading,
+            ime: self.ime,
+            atok: self.atok,
+            plist: self.plist,
+            jmdict: self.jmdict,
+            jmnedict: self.jmnedict,
+            ken_all: self.ken_all,
+            mozcsystemdict: self.mozcsystemdict,
+            source_id_def: self.source_id_def,
+            migrate: self.migrate,
+            aozora: self.aozora,
+            aozora_word_class: self
+                .aozora_word_class
+                .unwrap_or_else(|| "名詞,普通名詞,一般,*,*,*".to_owned()),
+            json: self.json,
+            json_pronunciation: self
+                .json_pronunciation
+                .unwrap_or_else(|| "reading".to_owned()),
+            json_notation: self.json_notation.unwrap_or_else(|| "surface".to_owned()),
+            json_word_class: self.json_word_class.unwrap_or_else(|| "pos".to_owned()),
+            json_frequency: if self.json_cost.is_some() {
+                self.json_frequency
+            } else {
+                Some(self.json_frequency.unwrap_or_else(|| "freq".to_owned()))
+            },
+            json_cost: self.json_cost,
+            output: self.output,
+            // user_dictionary.dbの品詞名は、ユーザー辞書型式で求める。
+            user_dict: self.user_dict || self.user_db,
+            plist_output: self.plist_output,
+            user_db: self.user_db,
+            user_db_name: self
+                .user_db_name
+                .unwrap_or_else(|| "dict-to-mozc".to_owned()),
+            split_entries: self.split,
+            split_order,
+            partitions,
+            shards: self.shards,
+            shard_sort,
+            shard_prefix: self
+                .shard_prefix
+                .unwrap_or_else(|| DEFAULT_SHARD_PREFIX.to_owned()),
+            shard_clean: self.shard_clean,
             places: self.places,
             symbols: self.symbols,
             debug: self.debug.unwrap_or_else(|| dict_type.default_debug()),
-        })
+        };
+        // 列名の指定は、ヘッダー行から位置を求める。
+        config.resolve_columns(&columns)?;
+        Ok(config)
     }
 }
 
@@ -203,6 +556,18 @@ impl DictType {
             DictType::NEologd => 10,
             DictType::UTDict => 0,
             DictType::MozcUserDict => 0,
+            DictType::Skk => 0,
+            DictType::Canna => 0,
+            DictType::Mecab => 0,
+            DictType::Ime => 0,
+            DictType::Atok => 0,
+            DictType::Plist => 0,
+            DictType::Jmdict => 0,
+            DictType::Jmnedict => 0,
+            DictType::KenAll => 0,
+            DictType::MozcSystemDict => 0,
+            DictType::Aozora => 0,
+            DictType::Json => 0,
         }
     }
 
@@ -213,6 +578,18 @@ impl DictType {
             DictType::NEologd => 12,
             DictType::UTDict => 4,
             DictType::MozcUserDict => 1,
+            DictType::Skk => 1,
+            DictType::Canna => 1,
+            DictType::Mecab => 1,
+            DictType::Ime => 1,
+            DictType::Atok => 1,
+            DictType::Plist => 1,
+            DictType::Jmdict => 1,
+            DictType::Jmnedict => 1,
+            DictType::KenAll => 1,
+            DictType::MozcSystemDict => 1,
+            DictType::Aozora => 1,
+            DictType::Json => 1,
         }
     }
 
@@ -223,6 +600,18 @@ impl DictType {
             DictType::NEologd => 4,
             DictType::UTDict => 1,
             DictType::MozcUserDict => 2,
+            DictType::Skk => 3,
+            DictType::Canna => 3,
+            DictType::Mecab => 3,
+            DictType::Ime => 2,
+            DictType::Atok => 3,
+            DictType::Plist => 2,
+            DictType::Jmdict => 3,
+            DictType::Jmnedict => 3,
+            DictType::KenAll => 3,
+            DictType::MozcSystemDict => 3,
+            DictType::Aozora => 3,
+            DictType::Json => 3,
         }
     }
 
@@ -233,6 +622,18 @@ impl DictType {
             DictType::NEologd => 6,
             DictType::UTDict => 1,
             DictType::MozcUserDict => 1,
+            DictType::Skk => 6,
+            DictType::Canna => 7,
+            DictType::Mecab => 9,
+            DictType::Ime => 1,
+            DictType::Atok => 7,
+            DictType::Plist => 0,
+            DictType::Jmdict => 6,
+            DictType::Jmnedict => 1,
+            DictType::KenAll => 7,
+            DictType::MozcSystemDict => 9,
+            DictType::Aozora => 6,
+            DictType::Json => 6,
         }
     }
 
@@ -243,6 +644,18 @@ impl DictType {
             DictType::NEologd => 3,
             DictType::UTDict => 3,
             DictType::MozcUserDict => 3,
+            DictType::Skk => 2,
+            DictType::Canna => 2,
+            DictType::Mecab => 2,
+            DictType::Ime => 3,
+            DictType::Atok => 2,
+            DictType::Plist => 2,
+            DictType::Jmdict => 2,
+            DictType::Jmnedict => 2,
+            DictType::KenAll => 2,
+            DictType::MozcSystemDict => 2,
+            DictType::Aozora => 2,
+            DictType::Json => 2,
         }
     }
 
@@ -253,6 +666,40 @@ impl DictType {
             DictType::NEologd => ",".to_owned(),
             DictType::UTDict => "\t".to_owned(),
             DictType::MozcUserDict => "\t".to_owned(),
+            DictType::Skk => ",".to_owned(),
+            DictType::Canna => ",".to_owned(),
+            DictType::Mecab => ",".to_owned(),
+            DictType::Ime => "\t".to_owned(),
+            DictType::Atok => "\t".to_owned(),
+            DictType::Plist => ",".to_owned(),
+            DictType::Jmdict => ",".to_owned(),
+            DictType::Jmnedict => ",".to_owned(),
+            DictType::KenAll => ",".to_owned(),
+            DictType::MozcSystemDict => "\t".to_owned(),
+            DictType::Aozora => ",".to_owned(),
+            DictType::Json => ",".to_owned(),
+        }
+    }
+
+    fn default_glob(&self) -> String {
+        match self {
+            DictType::Default => "*.csv".to_owned(),
+            DictType::Sudachi => "*.csv".to_owned(),
+            DictType::NEologd => "*.csv".to_owned(),
+            DictType::UTDict => "*.txt".to_owned(),
+            DictType::MozcUserDict => "*.txt".to_owned(),
+            DictType::Skk => "SKK-JISYO*".to_owned(),
+            DictType::Canna => "*.ctd".to_owned(),
+            DictType::Mecab => "*.csv".to_owned(),
+            DictType::Ime => "*.txt".to_owned(),
+            DictType::Atok => "*.txt".to_owned(),
+            DictType::Plist => "*.plist".to_owned(),
+            DictType::Jmdict => "JMdict*".to_owned(),
+            DictType::Jmnedict => "JMnedict*".to_owned(),
+            DictType::KenAll => "*.csv".to_owned(),
+            DictType::MozcSystemDict => "dictionary*.txt".to_owned(),
+            DictType::Aozora => "*.txt".to_owned(),
+            DictType::Json => "*.json*".to_owned(),
         }
     }
 
@@ -263,6 +710,18 @@ impl DictType {
             DictType::NEologd => 0,
             DictType::UTDict => 0,
             DictType::MozcUserDict => 0,
+            DictType::Skk => 0,
+            DictType::Canna => 0,
+            DictType::Mecab => 0,
+            DictType::Ime => 0,
+            DictType::Atok => 0,
+            DictType::Plist => 0,
+            DictType::Jmdict => 0,
+            DictType::Jmnedict => 0,
+            DictType::KenAll => 0,
+            DictType::MozcSystemDict => 0,
+            DictType::Aozora => 0,
+            DictType::Json => 0,
         }
     }
 }
@@ -313,8 +772,8 @@ pub fn main() -> ExitCode {
     // argsを使ってconfigを生成
     let config = match args.into_config() {
         Ok(config) => config,
-        Err(_) => {
-            eprintln!("Failed to parse config");
+        Err(e) => {
+            eprintln!("Failed to parse config: {}", e);
             return ExitCode::from(3); // configのパースに失敗した場合の終了コード
         }
     };
@@ -332,17 +791,36 @@ pub fn main() -> ExitCode {
     }
 
     // ファイルの存在チェック
-    if !config.csv_file.exists() {
-        eprintln!("Error: CSV file not found at {:?}", &config.csv_file);
-        return ExitCode::from(4);
+    for csv_file in &config.csv_files {
+        if csv_file.as_os_str() != STDIN_PATH && !csv_file.exists() {
+            eprintln!("Error: CSV file not found at {:?}", csv_file);
+            return ExitCode::from(4);
+        }
     }
 
     // ファイルの存在チェック
     if !config.id_def.exists() {
-        eprintln!("Error: id.def file not found at {:?}", &config.id_def);
+        eprintln!("Error: id.def file not found at {:?}", config.id_def);
+        return ExitCode::from(5);
+    }
+
+    // ファイルの存在チェック
+    if let Some(synonyms) = &config.sudachi_synonyms
+        && !synonyms.exists()
+    {
+        eprintln!("Error: synonyms file not found at {:?}", synonyms);
         return ExitCode::from(5);
     }
 
+    // 品詞IDの移行は、辞書を変換せずに書き換えるだけ
+    if config.migrate {
+        if let Err(e) = migrate_dictionary(&config) {
+            eprintln!("Failed to migrate: {}", e);
+            return ExitCode::FAILURE;
+        }
+        return ExitCode::SUCCESS;
+    }
+
     let mut dict_data = DictionaryData::new();
 
     // 辞書の読み込み処理
@@ -354,13 +832,66 @@ pub fn main() -> ExitCode {
         Box::new(UtDictProcessor)
     } else if config.mozcuserdict {
         Box::new(MozcUserDictProcessor)
+    } else if config.skk {
+        Box::new(SkkProcessor)
+    } else if config.canna {
+        Box::new(CannaProcessor)
+    } else if config.mecab {
+        Box::new(MecabProcessor)
+    } else if config.ime {
+        Box::new(ImeDictProcessor)
+    } else if config.atok {
+        Box::new(AtokProcessor)
+    } else if config.plist {
+        Box::new(PlistProcessor)
+    } else if config.jmdict {
+        Box::new(JmdictProcessor)
+    } else if config.jmnedict {
+        Box::new(JmnedictProcessor)
+    } else if config.ken_all {
+        Box::new(KenAllProcessor)
+    } else if config.mozcsystemdict {
+        Box::new(MozcSystemDictProcessor)
+    } else if config.aozora {
+        Box::new(AozoraProcessor)
+    } else if config.json {
+        Box::new(JsonProcessor)
     } else {
         Box::new(DefaultProcessor)
     };
 
-    let _ = process_dictionary(_processor.as_ref(), &mut dict_data, &config);
+    if let Err(e) = process_dictionary(_processor.as_ref(), &mut dict_data, &config) {
+        eprintln!("Failed to read the dictionary: {}", e);
+        return ExitCode::FAILURE;
+    }
 
-    let _ = dict_data.output(config.user_dict);
+    // 出力ファイルは、すべて書き込めた場合だけ作られる。
+    // 複数のファイルに分ける場合も、すべて書き込めてから作る。前の出力の要らなくなったファイルは削除する(--shardsでは--shard-cleanの指定があるときだけ)。
+    let result = match &config.output {
+        // 番号の付いたファイルに分ける。
+        Some(output) if config.split_entries.is_some() && !config.user_db => {
+            dict_data.output_split(output, &config)
+        }
+        // MozcのOSS辞書のように、ディレクトリのdictionary00.txt ... に分ける。
+        Some(output) if config.shards.is_some() => dict_data.output_shards(output, &config),
+        // 分類ごとのファイルに分ける。
+        Some(output) if !config.partitions.is_empty() => {
+            dict_data.output_partitions(output, &config)
+        }
+        output => write_output(output.as_deref(), |writer| {
+            if config.user_db {
+                dict_data.output_user_db(writer, &config)
+            } else if config.plist_output {
+                dict_data.output_plist(writer, config.user_dict)
+            } else {
+                dict_data.output(writer, config.user_dict)
+            }
+        }),
+    };
+    if let Err(e) = result {
+        eprintln!("Failed to write the dictionary: {}", e);
+        return ExitCode::FAILURE;
+    }
 
     if config.debug > 0 {
         let elp = now.elapsed();